@@ -0,0 +1,27 @@
+use std::env;
+use std::fs;
+use std::path::Path;
+
+/// Packs every five letter word from the word lists into `$OUT_DIR/words5.bin` so the wasm build can
+/// check guesses without downloading the full dictionary. Each word is stored as its letters read as
+/// a base 26 number (26^5 fits in 24 bits), as 3 big-endian bytes, sorted for binary search.
+fn main() {
+    println!("cargo:rerun-if-changed=data/dictionary.txt");
+    println!("cargo:rerun-if-changed=data/answers.txt");
+
+    let mut packed: Vec<u32> = Vec::new();
+    for filename in ["data/dictionary.txt", "data/answers.txt"] {
+        let words = fs::read_to_string(filename).expect("Failed to read word list");
+        for word in words.lines().map(|w| w.trim().to_uppercase()) {
+            if word.len() == 5 && word.bytes().all(|b| b.is_ascii_uppercase()) {
+                packed.push(word.bytes().fold(0, |n, b| n * 26 + (b - b'A') as u32));
+            }
+        }
+    }
+    packed.sort();
+    packed.dedup();
+
+    let bytes: Vec<u8> = packed.iter().flat_map(|n| n.to_be_bytes()[1..].to_vec()).collect();
+    let out_dir = env::var("OUT_DIR").expect("OUT_DIR is set by cargo");
+    fs::write(Path::new(&out_dir).join("words5.bin"), bytes).expect("Failed to write packed words");
+}
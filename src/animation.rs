@@ -0,0 +1,172 @@
+use std::time::{Duration, Instant};
+
+/// How often the screen is redrawn while something is moving.
+pub const FRAME: Duration = Duration::from_millis(30);
+/// How long one tile takes to turn over, and how long after one starts the next one does.
+const FLIP: Duration = Duration::from_millis(300);
+const FLIP_STAGGER: Duration = Duration::from_millis(250);
+/// Columns the row moves sideways by, one entry per shake frame.
+const SHAKE: [i16; 8] = [-2, 2, -2, 2, -1, 1, -1, 0];
+const SHAKE_FRAME: Duration = Duration::from_millis(50);
+const BOUNCE: Duration = Duration::from_millis(200);
+const BOUNCE_STAGGER: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    /// A guess's colours, revealed a tile at a time.
+    Reveal,
+    /// The row being typed, for a guess that wasn't accepted.
+    Shake,
+    /// The winning row, a tile at a time.
+    Bounce,
+}
+
+/// How a tile looks at one point in an animation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TileFrame {
+    /// Still showing the letter as it was typed, before turning over.
+    Hidden,
+    /// Halfway over, so edge on and showing nothing.
+    Edge,
+    Shown,
+    /// A row above where it belongs.
+    Raised,
+}
+
+/// Something happening to one row of the board.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Animation {
+    pub kind: Kind,
+    pub row: usize,
+    tiles: usize,
+    started: Instant,
+}
+
+impl Animation {
+    pub fn reveal(row: usize, tiles: usize, now: Instant) -> Self {
+        Animation {
+            kind: Kind::Reveal,
+            row,
+            tiles,
+            started: now,
+        }
+    }
+
+    pub fn shake(row: usize, now: Instant) -> Self {
+        Animation {
+            kind: Kind::Shake,
+            row,
+            tiles: 0,
+            started: now,
+        }
+    }
+
+    pub fn bounce(row: usize, tiles: usize, now: Instant) -> Self {
+        Animation {
+            kind: Kind::Bounce,
+            row,
+            tiles,
+            started: now,
+        }
+    }
+
+    fn duration(&self) -> Duration {
+        let last_tile = self.tiles.saturating_sub(1) as u32;
+        match self.kind {
+            Kind::Reveal => FLIP_STAGGER * last_tile + FLIP,
+            Kind::Shake => SHAKE_FRAME * SHAKE.len() as u32,
+            Kind::Bounce => BOUNCE_STAGGER * last_tile + BOUNCE,
+        }
+    }
+
+    pub fn is_finished(&self, now: Instant) -> bool {
+        now >= self.started + self.duration()
+    }
+
+    /// Columns to move `row` sideways by.
+    pub fn offset(&self, row: usize, now: Instant) -> i16 {
+        if self.kind != Kind::Shake || row != self.row {
+            return 0;
+        }
+        let frame = now.saturating_duration_since(self.started).as_millis() / SHAKE_FRAME.as_millis();
+        SHAKE.get(frame as usize).copied().unwrap_or(0)
+    }
+
+    /// How the tile in `column` of `row` looks.
+    pub fn tile(&self, row: usize, column: usize, now: Instant) -> TileFrame {
+        if row != self.row {
+            return TileFrame::Shown;
+        }
+        let elapsed = now.saturating_duration_since(self.started);
+        match self.kind {
+            Kind::Reveal => {
+                let start = FLIP_STAGGER * column as u32;
+                if elapsed < start {
+                    TileFrame::Hidden
+                } else if elapsed < start + FLIP / 2 {
+                    TileFrame::Edge
+                } else {
+                    TileFrame::Shown
+                }
+            }
+            Kind::Bounce => {
+                let start = BOUNCE_STAGGER * column as u32;
+                if elapsed >= start && elapsed < start + BOUNCE {
+                    TileFrame::Raised
+                } else {
+                    TileFrame::Shown
+                }
+            }
+            Kind::Shake => TileFrame::Shown,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use crate::animation::{Animation, TileFrame};
+
+    fn ms(n: u64) -> Duration {
+        Duration::from_millis(n)
+    }
+
+    #[test]
+    fn reveals_a_tile_at_a_time() {
+        let start = Instant::now();
+        let reveal = Animation::reveal(2, 5, start);
+        assert_eq!(reveal.tile(2, 0, start), TileFrame::Edge);
+        assert_eq!(reveal.tile(2, 1, start), TileFrame::Hidden);
+        assert_eq!(reveal.tile(2, 0, start + ms(200)), TileFrame::Shown);
+        assert_eq!(reveal.tile(2, 1, start + ms(300)), TileFrame::Edge);
+        assert_eq!(reveal.tile(2, 4, start + ms(900)), TileFrame::Hidden);
+        assert_eq!(reveal.tile(1, 4, start), TileFrame::Shown);
+        assert!(!reveal.is_finished(start + ms(1200)));
+        assert!(reveal.is_finished(start + ms(1300)));
+        assert_eq!(reveal.tile(2, 4, start + ms(1300)), TileFrame::Shown);
+    }
+
+    #[test]
+    fn shakes_the_row_and_settles() {
+        let start = Instant::now();
+        let shake = Animation::shake(0, start);
+        assert_eq!(shake.offset(0, start), -2);
+        assert_eq!(shake.offset(0, start + ms(60)), 2);
+        assert_eq!(shake.offset(1, start + ms(60)), 0);
+        assert_eq!(shake.tile(0, 0, start), TileFrame::Shown);
+        assert!(shake.is_finished(start + ms(400)));
+        assert_eq!(shake.offset(0, start + ms(400)), 0);
+    }
+
+    #[test]
+    fn bounces_each_tile_in_turn() {
+        let start = Instant::now();
+        let bounce = Animation::bounce(3, 5, start);
+        assert_eq!(bounce.tile(3, 0, start), TileFrame::Raised);
+        assert_eq!(bounce.tile(3, 1, start), TileFrame::Shown);
+        assert_eq!(bounce.tile(3, 1, start + ms(150)), TileFrame::Raised);
+        assert_eq!(bounce.tile(3, 0, start + ms(250)), TileFrame::Shown);
+        assert!(bounce.is_finished(start + ms(600)));
+    }
+}
@@ -0,0 +1,159 @@
+use serde_json::{json, Value};
+
+use crate::board::Board;
+use crate::theme::Theme;
+
+/// The `action_id`s of the buttons under the board, which come back in `block_actions` payloads.
+pub const NEW_GAME_ACTION: &str = "wordle_new_game";
+pub const HINT_ACTION: &str = "wordle_hint";
+
+/// The board as Block Kit blocks: the emoji grid with each guess beside it in code font, so it
+/// lines up in any font, then the guess count, the keyboard and buttons to carry on.
+pub fn board(board: &Board, theme: Theme) -> Value {
+    let mut blocks = summary(board, theme);
+    blocks.push(actions(board));
+    Value::Array(blocks)
+}
+
+/// The board without buttons, for the daily puzzle, which can't be restarted or hinted at.
+pub fn daily(board: &Board, theme: Theme) -> Value {
+    Value::Array(summary(board, theme))
+}
+
+/// Blocks for a message that's just text, such as a hint.
+pub fn text(text: &str) -> Value {
+    json!([section(text)])
+}
+
+fn summary(board: &Board, theme: Theme) -> Vec<Value> {
+    let mut blocks = vec![section(&grid(board, theme))];
+    blocks.push(context(&status(board)));
+    blocks.push(context(board.keyboard().slack(theme).trim_end()));
+    if let Some(result) = board.result_message() {
+        blocks.push(section(&format!("*{}*", result)));
+    }
+    blocks
+}
+
+fn grid(board: &Board, theme: Theme) -> String {
+    board
+        .rows
+        .iter()
+        .map(|row| {
+            let squares: String = row.iter().map(|c| theme.emoji(c)).collect();
+            let word: String = row.iter().map(|c| c.to_string()).collect();
+            if word.is_empty() {
+                squares
+            } else {
+                format!("{}  `{}`", squares, word.to_uppercase())
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// "Guess 2/6 · hard mode · 1 hint"
+fn status(board: &Board) -> String {
+    let mut parts = vec![format!("Guess {}/{}", board.guesses_used(), board.rows.len())];
+    if board.config().hard_mode {
+        parts.push("hard mode".to_string());
+    }
+    match board.hints().len() {
+        0 => {}
+        1 => parts.push("1 hint".to_string()),
+        n => parts.push(format!("{} hints", n)),
+    }
+    parts.join(" · ")
+}
+
+/// A finished game can only be followed by a new one, so it has no Hint button.
+fn actions(board: &Board) -> Value {
+    let mut elements = vec![button("New game", NEW_GAME_ACTION)];
+    if !board.is_over() {
+        elements.push(button("Hint", HINT_ACTION));
+    }
+    json!({ "type": "actions", "elements": elements })
+}
+
+fn button(label: &str, action_id: &str) -> Value {
+    json!({
+        "type": "button",
+        "text": { "type": "plain_text", "text": label },
+        "action_id": action_id,
+    })
+}
+
+fn section(text: &str) -> Value {
+    json!({ "type": "section", "text": { "type": "mrkdwn", "text": text } })
+}
+
+fn context(text: &str) -> Value {
+    json!({ "type": "context", "elements": [{ "type": "mrkdwn", "text": text }] })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::blocks::{self, HINT_ACTION, NEW_GAME_ACTION};
+    use crate::board::{Board, BoardConfig};
+    use crate::dictionary::WebDictionary;
+    use crate::theme::Theme;
+
+    fn board(word: &str) -> Board {
+        Board::with_dictionary(word.to_string(), BoardConfig::default(), Box::new(WebDictionary::new()))
+    }
+
+    fn action_ids(blocks: &serde_json::Value) -> Vec<&str> {
+        let actions = blocks.as_array().unwrap().last().unwrap();
+        assert_eq!(actions["type"], "actions");
+        actions["elements"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|e| e["action_id"].as_str().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn renders_a_game_in_progress() {
+        let mut board = board("rusty");
+        board.guess("trust").unwrap();
+        board.hint();
+        let blocks = blocks::board(&board, Theme::Standard);
+
+        assert_eq!(blocks[0]["type"], "section");
+        let grid = blocks[0]["text"]["text"].as_str().unwrap();
+        let rows: Vec<&str> = grid.lines().collect();
+        assert_eq!(rows.len(), 6);
+        assert_eq!(rows[0], "🟨🟨🟨🟨⬜  `TRUST`");
+        assert_eq!(rows[1], "⬛⬛⬛⬛⬛");
+
+        assert_eq!(blocks[1]["elements"][0]["text"], "Guess 1/6 · 1 hint");
+        assert!(blocks[2]["elements"][0]["text"]
+            .as_str()
+            .unwrap()
+            .starts_with("Q W E 🟨R 🟨T"));
+        assert_eq!(action_ids(&blocks), vec![NEW_GAME_ACTION, HINT_ACTION]);
+
+        let blocks = blocks::board(&board, Theme::Dark);
+        let grid = blocks[0]["text"]["text"].as_str().unwrap();
+        assert!(grid.starts_with("🟨🟨🟨🟨⬛  `TRUST`\n🔲🔲🔲🔲🔲\n"));
+    }
+
+    #[test]
+    fn renders_a_finished_game() {
+        let mut board = board("rusty");
+        board.set_hard_mode(true);
+        board.guess("rusty").unwrap();
+        let blocks = blocks::board(&board, Theme::Standard);
+        assert_eq!(blocks[1]["elements"][0]["text"], "Guess 1/6 · hard mode");
+        assert_eq!(blocks[3]["text"]["text"], "*You won in 1/6!*");
+        assert_eq!(action_ids(&blocks), vec![NEW_GAME_ACTION]);
+        assert_eq!(blocks::daily(&board, Theme::Standard).as_array().unwrap().len(), 4);
+    }
+
+    #[test]
+    fn renders_text() {
+        let blocks = blocks::text("The word contains R.");
+        assert_eq!(blocks[0]["text"]["text"], "The word contains R.");
+    }
+}
@@ -1,40 +1,187 @@
 use std::collections::HashMap;
 use std::fmt;
-use std::io::{self, Error, Write};
+use std::io::{self, Error, ErrorKind, Write};
 
-use crate::dictionary::{ComputerDictionary, DictionaryLike, WebDictionary};
+use crate::daily;
+use crate::dictionary::DictionaryLike;
+#[cfg(any(target_arch = "wasm32", test))]
+use crate::dictionary::WebDictionary;
+use crate::hint::{self, Hint};
+use crate::keyboard::Keyboard;
+use crate::theme::Theme;
+#[cfg(target_arch = "wasm32")]
 use crate::traits::WebComponent;
+use chrono::{DateTime, NaiveDate, Utc};
+use rand::RngCore;
+use serde::{Deserialize, Serialize, Serializer};
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::{fs, path::Path};
 
 #[cfg(not(target_arch = "wasm32"))]
 use crossterm::{
     cursor, queue, style,
-    style::Color,
     terminal::{self, ClearType},
 };
+#[cfg(target_arch = "wasm32")]
 use wasm_bindgen::JsValue;
 
 pub struct Board {
     word: String,
-    word_count: HashMap<char, i32>,
-    pub rows: [[Cell; 5]; 6],
+    pub rows: Vec<Vec<Cell>>,
     pub dictionary: Box<dyn DictionaryLike + Send>,
+    config: BoardConfig,
+    state: GameState,
+    puzzle: Option<u32>,
+    hints: Vec<Hint>,
+    keyboard: Keyboard,
+    started_at: DateTime<Utc>,
+    finished_at: Option<DateTime<Utc>>,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+/// Everything needed to rebuild a `Board`, minus the dictionary. Rows are stored as the words that
+/// were guessed and rescored on load, so a saved game can't disagree with the scoring.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedGame {
+    pub secret: String,
+    pub config: BoardConfig,
+    pub guesses: Vec<String>,
+    pub state: GameState,
+    pub puzzle: Option<u32>,
+    pub hints: Vec<Hint>,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+impl Serialize for Board {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_saved().serialize(serializer)
+    }
+}
+
+/// The rules of a game: how long the secret word is, how many rows there are to guess it and
+/// whether revealed hints have to be reused.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct BoardConfig {
+    pub word_length: usize,
+    pub max_guesses: usize,
+    pub hard_mode: bool,
+}
+
+impl Default for BoardConfig {
+    fn default() -> Self {
+        BoardConfig {
+            word_length: 5,
+            max_guesses: 6,
+            hard_mode: false,
+        }
+    }
+}
+
+/// Where a game is in its lifecycle. Only `InProgress` accepts guesses.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum GameState {
+    InProgress,
+    Won,
+    Lost,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
 pub enum Cell {
     Green(char),
     Yellow(char),
     Gray(char),
+    #[default]
     Empty,
 }
 
+/// What happened to a guess that was accepted onto the board.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GuessOutcome {
+    /// Every letter was green.
+    Solved,
+    /// The guess was scored and there are still rows left.
+    Incorrect,
+    /// The guess was scored and filled the last row.
+    OutOfGuesses,
+}
+
+/// Why a guess was rejected. The board is left untouched when this is returned.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GuessError {
+    TooShort {
+        expected: usize,
+        actual: usize,
+    },
+    TooLong {
+        expected: usize,
+        actual: usize,
+    },
+    NonAlphabetic(String),
+    NotInDictionary(String),
+    /// Hard mode: a green letter from an earlier guess was moved or dropped.
+    MustPlaceLetter {
+        position: usize,
+        letter: char,
+    },
+    /// Hard mode: a yellow letter from an earlier guess was dropped.
+    MustContainLetter(char),
+    GameOver,
+}
+
+impl fmt::Display for GuessError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GuessError::TooShort { expected, actual } => {
+                write!(
+                    f,
+                    "Not enough letters! Guesses must be {} letters, got {}.",
+                    expected, actual
+                )
+            }
+            GuessError::TooLong { expected, actual } => {
+                write!(
+                    f,
+                    "Too many letters! Guesses must be {} letters, got {}.",
+                    expected, actual
+                )
+            }
+            GuessError::NonAlphabetic(guess) => write!(f, "{} can only contain the letters A to Z!", guess),
+            GuessError::NotInDictionary(guess) => write!(f, "{} is not in the dictionary!", guess),
+            GuessError::MustPlaceLetter { position, letter } => write!(
+                f,
+                "{} letter must be {}",
+                ordinal(position + 1),
+                letter.to_ascii_uppercase()
+            ),
+            GuessError::MustContainLetter(letter) => {
+                write!(f, "Guess must contain {}", letter.to_ascii_uppercase())
+            }
+            GuessError::GameOver => write!(f, "The game is over, there are no guesses left!"),
+        }
+    }
+}
+
+impl std::error::Error for GuessError {}
+
+pub(crate) fn ordinal(n: usize) -> String {
+    let suffix = match (n % 10, n % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    };
+    format!("{}{}", n, suffix)
+}
+
 impl fmt::Display for Cell {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            &Cell::Gray(v) => write!(f, "{}", v),
-            &Cell::Yellow(v) => write!(f, "{}", v),
-            &Cell::Green(v) => write!(f, "{}", v),
-            &Cell::Empty => write!(f, "{}", ""),
+            Cell::Gray(v) => write!(f, "{}", v),
+            Cell::Yellow(v) => write!(f, "{}", v),
+            Cell::Green(v) => write!(f, "{}", v),
+            Cell::Empty => Ok(()),
         }
     }
 }
@@ -45,16 +192,12 @@ impl Cell {
     }
 }
 
+#[cfg(target_arch = "wasm32")]
 impl WebComponent for Cell {
-    fn to_dom(&self, document: &web_sys::Document) -> Result<web_sys::Element, JsValue> {
-        let cell_style = "display: flex; align-items: center; justify-content: center; font-size: 24px; margin: 4px; width: 50px; height: 50px; padding: 4px; border: 2px solid #d3d6da;";
+    fn to_dom(&self, document: &web_sys::Document, theme: Theme) -> Result<web_sys::Element, JsValue> {
+        let cell_style = "display: flex; align-items: center; justify-content: center; font-size: 24px; margin: 4px; width: 50px; height: 50px; padding: 4px; border: 2px solid;";
         let cell_div: web_sys::Element = document.create_element("div")?;
-        match *&self {
-            Cell::Gray(_) => cell_div.set_attribute("style", &format!("{} background-color: #86888a;", cell_style)),
-            Cell::Green(_) => cell_div.set_attribute("style", &format!("{} background-color: #6aaa64;", cell_style)),
-            Cell::Yellow(_) => cell_div.set_attribute("style", &format!("{} background-color: #c9b458;", cell_style)),
-            Cell::Empty => cell_div.set_attribute("style", cell_style),
-        }?;
+        cell_div.set_attribute("style", &format!("{} {}", cell_style, theme.css(self).style()))?;
         cell_div.set_text_content(Some(&format!("{}", &self)));
         Ok(cell_div)
     }
@@ -69,8 +212,56 @@ fn count_chars(a: &str) -> HashMap<char, i32> {
     count
 }
 
+/// Colours every letter of `guess` against the secret `word`. The board, the solver and hints all
+/// score through here so they can never disagree.
+pub fn score(word: &str, guess: &str) -> Vec<Cell> {
+    let mut word_count = count_chars(word);
+    let mut row = vec![Cell::new(); guess.chars().count()];
+    mark_green(&mut row, &mut word_count, word, guess);
+    mark_yellow(&mut row, &mut word_count, word, guess);
+    mark_gray(&mut row, guess);
+    row
+}
+
+fn mark_green(row: &mut [Cell], word_count: &mut HashMap<char, i32>, word: &str, guess: &str) {
+    let chars = guess.chars();
+    for (guess_idx, c) in chars.enumerate() {
+        for (word_idx, letter) in word.chars().enumerate() {
+            let count = word_count.get_mut(&letter).unwrap();
+            if guess_idx == word_idx && c == letter {
+                *count -= 1;
+                row[guess_idx] = Cell::Green(letter);
+            }
+        }
+    }
+}
+
+fn mark_yellow(row: &mut [Cell], word_count: &mut HashMap<char, i32>, word: &str, guess: &str) {
+    let chars = guess.chars();
+    for (guess_idx, c) in chars.enumerate() {
+        for (word_idx, letter) in word.chars().enumerate() {
+            let count = word_count.get_mut(&letter).unwrap();
+            if *count > 0 && guess_idx != word_idx && c == letter {
+                *count -= 1;
+                row[guess_idx] = Cell::Yellow(letter);
+            }
+        }
+    }
+}
+
+fn mark_gray(row: &mut [Cell], guess: &str) {
+    let chars = guess.chars();
+    for (guess_idx, c) in chars.enumerate() {
+        let cell = row[guess_idx];
+        if cell == Cell::Empty {
+            row[guess_idx] = Cell::Gray(c);
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
 impl WebComponent for Board {
-    fn to_dom(&self, document: &web_sys::Document) -> Result<web_sys::Element, JsValue> {
+    fn to_dom(&self, document: &web_sys::Document, theme: Theme) -> Result<web_sys::Element, JsValue> {
         let container: web_sys::Element = document.create_element("div")?;
         container.set_attribute(
             "style",
@@ -81,102 +272,353 @@ impl WebComponent for Board {
             let row_div: web_sys::Element = document.create_element("div")?;
             row_div.set_attribute("style", "display: flex; flex-direction: row; flex-shrink: 1")?;
             for cell in row.iter() {
-                let cell_div = cell.to_dom(document)?;
+                let cell_div = cell.to_dom(document, theme)?;
                 row_div.append_child(&cell_div)?;
             }
             container.append_child(&row_div)?;
         }
+        let keyboard_div = self.keyboard.to_dom(document, theme)?;
+        container.append_child(&keyboard_div)?;
         Ok(container)
     }
 }
 
 impl Board {
     // self, &self, &mut self
-    pub fn new(word: String) -> Self {
-        let word_count = count_chars(&word);
+    /// A game with a secret word drawn from the dictionary's answers, or `None` if it has no
+    /// answers of the configured length.
+    pub fn random(
+        config: BoardConfig,
+        dictionary: Box<dyn DictionaryLike + Send>,
+        rng: &mut dyn RngCore,
+    ) -> Option<Self> {
+        let word = dictionary.random_answer(config.word_length, rng)?;
+        Some(Board::with_dictionary(word, config, dictionary))
+    }
+
+    #[cfg(any(target_arch = "wasm32", test))]
+    pub fn new_wasm(word: String, config: BoardConfig) -> Self {
+        Board::with_dictionary(word, config, Box::new(WebDictionary::new()))
+    }
+
+    /// # Panics
+    ///
+    /// If `word` isn't `config.word_length` letters long.
+    pub fn with_dictionary(word: String, config: BoardConfig, dictionary: Box<dyn DictionaryLike + Send>) -> Self {
+        assert_eq!(
+            word.chars().count(),
+            config.word_length,
+            "the secret word must be {} letters",
+            config.word_length
+        );
         Board {
-            word: word,
-            word_count: word_count,
-            rows: [[Cell::new(); 5]; 6],
-            dictionary: Box::new(ComputerDictionary::new("./data/dictionary.txt")),
+            word,
+            rows: vec![vec![Cell::new(); config.word_length]; config.max_guesses],
+            dictionary,
+            config,
+            state: GameState::InProgress,
+            puzzle: None,
+            hints: vec![],
+            keyboard: Keyboard::new(),
+            started_at: Utc::now(),
+            finished_at: None,
         }
     }
 
-    pub fn new_wasm(word: String) -> Self {
-        let word_count = count_chars(&word);
-        Board {
-            word: word,
-            word_count: word_count,
-            rows: [[Cell::new(); 5]; 6],
-            dictionary: Box::new(WebDictionary::new()),
+    pub fn to_saved(&self) -> SavedGame {
+        SavedGame {
+            secret: self.word.clone(),
+            config: self.config,
+            guesses: self.guesses(),
+            state: self.state,
+            puzzle: self.puzzle,
+            hints: self.hints.clone(),
+            started_at: self.started_at,
+            finished_at: self.finished_at,
         }
     }
 
-    fn mark_green(&mut self, index: usize, guess: &str) {
-        let chars = guess.chars();
-        for (guess_idx, c) in chars.enumerate() {
-            for (word_idx, letter) in self.word.chars().enumerate() {
-                let count = self.word_count.get_mut(&letter).unwrap();
-                if guess_idx == word_idx && c == letter {
-                    *count -= 1;
-                    self.rows[index][guess_idx] = Cell::Green(letter);
-                }
+    /// Rebuilds a saved game. Guesses aren't checked against `dictionary` again, so a game saved
+    /// before a word list change still loads.
+    pub fn from_saved(saved: SavedGame, dictionary: Box<dyn DictionaryLike + Send>) -> io::Result<Self> {
+        let config = saved.config;
+        let invalid = saved.secret.chars().count() != config.word_length
+            || saved.guesses.len() > config.max_guesses
+            || saved.guesses.iter().any(|g| g.chars().count() != config.word_length);
+        if invalid {
+            return Err(Error::new(ErrorKind::InvalidData, "saved game doesn't fit its board"));
+        }
+        let mut board = Board::with_dictionary(saved.secret, config, dictionary);
+        // the result comes from the guesses, so a save can't claim a win it doesn't have or leave a
+        // full board in progress. Only giving up ends a game with rows to spare.
+        let mut state = GameState::InProgress;
+        for (index, guess) in saved.guesses.iter().enumerate() {
+            if state != GameState::InProgress {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    "saved game has guesses after it ended",
+                ));
             }
+            board.rows[index] = score(&board.word, guess);
+            board.keyboard.update(&board.rows[index]);
+            if board.rows[index].iter().all(|c| matches!(c, Cell::Green(_))) {
+                state = GameState::Won;
+            } else if index == board.rows.len() - 1 {
+                state = GameState::Lost;
+            }
+        }
+        let gave_up = state == GameState::InProgress && saved.state == GameState::Lost;
+        if state != saved.state && !gave_up {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "saved game's result doesn't match its guesses",
+            ));
+        }
+        board.state = saved.state;
+        board.puzzle = saved.puzzle;
+        board.hints = saved.hints;
+        board.started_at = saved.started_at;
+        board.finished_at = saved.finished_at;
+        Ok(board)
+    }
+
+    /// Writes the game to `path` as JSON, creating its directory if needed.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let path = path.as_ref();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let json = serde_json::to_string_pretty(self)?;
+        // write then rename, so a crash mid-write can't leave a half-written save behind
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, json)?;
+        fs::rename(tmp, path)
+    }
+
+    /// Reads a game written by `save`, with `dictionary` for the guesses still to come.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load_with_dictionary<P: AsRef<Path>>(
+        path: P,
+        dictionary: Box<dyn DictionaryLike + Send>,
+    ) -> io::Result<Self> {
+        let saved: SavedGame = serde_json::from_str(&fs::read_to_string(path)?)?;
+        Board::from_saved(saved, dictionary)
+    }
+
+    pub fn started_at(&self) -> DateTime<Utc> {
+        self.started_at
+    }
+
+    pub fn finished_at(&self) -> Option<DateTime<Utc>> {
+        self.finished_at
+    }
+
+    /// Today's puzzle for `date`: everyone playing the same date and word length gets the same answer.
+    /// Returns `None` for dates before the first puzzle or if there are no answers of that length.
+    pub fn daily(date: NaiveDate, config: BoardConfig, dictionary: Box<dyn DictionaryLike + Send>) -> Option<Self> {
+        let puzzle = daily::puzzle_number(date)?;
+        let word = daily::answer(dictionary.answers(config.word_length), puzzle)?.to_lowercase();
+        let mut board = Board::with_dictionary(word, config, dictionary);
+        board.puzzle = Some(puzzle);
+        Some(board)
+    }
+
+    /// The daily puzzle number, or `None` for a random game.
+    pub fn puzzle_number(&self) -> Option<u32> {
+        self.puzzle
+    }
+
+    pub fn config(&self) -> BoardConfig {
+        self.config
+    }
+
+    // self, &self, mut self, &mut self
+    pub fn guess(&mut self, guess: &str) -> Result<GuessOutcome, GuessError> {
+        if self.state != GameState::InProgress {
+            return Err(GuessError::GameOver);
+        }
+        let index = self.guesses_used();
+        let guess = guess.trim().to_lowercase();
+        if !guess.chars().all(|c| c.is_ascii_alphabetic()) {
+            return Err(GuessError::NonAlphabetic(guess));
+        }
+        let expected = self.config.word_length;
+        let actual = guess.chars().count();
+        if actual < expected {
+            return Err(GuessError::TooShort { expected, actual });
+        }
+        if actual > expected {
+            return Err(GuessError::TooLong { expected, actual });
+        }
+        if !self.dictionary.is_allowed_guess(&guess) {
+            return Err(GuessError::NotInDictionary(guess));
+        }
+        if self.config.hard_mode {
+            self.check_hard_mode(&guess)?;
+        }
+
+        self.rows[index] = score(&self.word, &guess);
+        self.keyboard.update(&self.rows[index]);
+
+        if self.rows[index].iter().all(|c| matches!(c, Cell::Green(_))) {
+            self.state = GameState::Won;
+            self.finished_at = Some(Utc::now());
+            Ok(GuessOutcome::Solved)
+        } else if index == self.rows.len() - 1 {
+            self.state = GameState::Lost;
+            self.finished_at = Some(Utc::now());
+            Ok(GuessOutcome::OutOfGuesses)
+        } else {
+            Ok(GuessOutcome::Incorrect)
         }
     }
 
-    fn mark_yellow(&mut self, index: usize, guess: &str) {
-        let chars = guess.chars();
-        for (guess_idx, c) in chars.enumerate() {
-            for (word_idx, letter) in self.word.chars().enumerate() {
-                let count = self.word_count.get_mut(&letter).unwrap();
-                if *count > 0 && guess_idx != word_idx && c == letter {
-                    *count -= 1;
-                    self.rows[index][guess_idx] = Cell::Yellow(letter);
+    /// Every green letter has to stay where it is, and every yellow letter has to show up somewhere.
+    fn check_hard_mode(&self, guess: &str) -> Result<(), GuessError> {
+        let letters: Vec<char> = guess.chars().collect();
+        let guess_count = count_chars(guess);
+        for row in self.rows.iter().take(self.guesses_used()) {
+            for (position, cell) in row.iter().enumerate() {
+                if let &Cell::Green(letter) = cell {
+                    if letters[position] != letter {
+                        return Err(GuessError::MustPlaceLetter { position, letter });
+                    }
+                }
+            }
+            let mut revealed: HashMap<char, i32> = HashMap::new();
+            for cell in row {
+                if let &Cell::Green(letter) | &Cell::Yellow(letter) = cell {
+                    *revealed.entry(letter).or_insert(0) += 1;
+                }
+            }
+            for cell in row {
+                if let &Cell::Yellow(letter) = cell {
+                    if guess_count.get(&letter).unwrap_or(&0) < &revealed[&letter] {
+                        return Err(GuessError::MustContainLetter(letter));
+                    }
                 }
             }
         }
+        Ok(())
     }
 
-    fn mark_gray(&mut self, index: usize, guess: &str) {
-        let chars = guess.chars();
-        for (guess_idx, c) in chars.enumerate() {
-            let cell = self.rows[index][guess_idx];
-            if cell == Cell::Empty {
-                self.rows[index][guess_idx] = Cell::Gray(c);
-            }
+    /// Hard mode can be turned off at any time, but only turned on before the first guess.
+    /// Returns whether the change was applied.
+    pub fn set_hard_mode(&mut self, hard_mode: bool) -> bool {
+        if hard_mode && self.guesses_used() > 0 && !self.config.hard_mode {
+            return false;
         }
+        self.config.hard_mode = hard_mode;
+        true
     }
 
-    // self, &self, mut self, &mut self
-    pub fn guess(&mut self, guess: &str) {
-        let index = self.rows.iter().position(|&r| r[0] == Cell::Empty).expect("You lose!");
-        let guess = guess.trim();
-        if guess.len() > 5 {
-            panic!("can't guess more than 5")
+    /// The next hint for a stuck player, each one more revealing than the last. Hints are recorded
+    /// so the share text can own up to them. Returns `None` once the game is over.
+    pub fn hint(&mut self) -> Option<Hint> {
+        if self.is_over() {
+            return None;
+        }
+        let hint = hint::next_hint(&self.word, self, self.hints.len())?;
+        self.hints.push(hint.clone());
+        Some(hint)
+    }
+
+    /// Ends the game as a loss so the answer can be revealed. Returns false if it was already over.
+    pub fn give_up(&mut self) -> bool {
+        if self.is_over() {
+            return false;
         }
-        self.word_count = count_chars(&self.word);
-        self.mark_green(index, guess);
-        self.mark_yellow(index, guess);
-        self.mark_gray(index, guess);
+        self.state = GameState::Lost;
+        self.finished_at = Some(Utc::now());
+        true
+    }
+
+    pub fn keyboard(&self) -> &Keyboard {
+        &self.keyboard
+    }
+
+    pub fn hints(&self) -> &[Hint] {
+        &self.hints
+    }
+
+    #[cfg(test)]
+    pub fn state(&self) -> GameState {
+        self.state
     }
 
     pub fn has_won(&self) -> bool {
-        // Check each row
-        // Make sure every cell is green and has the right letter
-        // Return true if any row matches that
-        return self.rows.iter().any(|&r| {
-            return r.iter().all(|c| {
-                let is_green_cell = match c {
-                    Cell::Green(_) => true,
-                    _ => false,
-                };
-                return is_green_cell;
-            });
-        });
+        self.state == GameState::Won
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.state != GameState::InProgress
+    }
+
+    /// The words guessed so far, in order.
+    pub fn guesses(&self) -> Vec<String> {
+        self.rows
+            .iter()
+            .take(self.guesses_used())
+            .map(|r| r.iter().map(|c| c.to_string()).collect())
+            .collect()
+    }
+
+    pub fn guesses_used(&self) -> usize {
+        self.rows.iter().take_while(|r| r[0] != Cell::Empty).count()
     }
 
-    pub fn slack(&self) -> String {
+    pub fn remaining_guesses(&self) -> usize {
+        self.rows.len() - self.guesses_used()
+    }
+
+    /// The secret word, but only once there is nothing left to spoil.
+    pub fn reveal_answer(&self) -> Option<&str> {
+        if self.is_over() {
+            Some(&self.word)
+        } else {
+            None
+        }
+    }
+
+    /// A line every front-end can show when the game ends, e.g. "You won in 3/6!".
+    pub fn result_message(&self) -> Option<String> {
+        match self.state {
+            GameState::InProgress => None,
+            GameState::Won => Some(format!("You won in {}/{}!", self.guesses_used(), self.rows.len())),
+            GameState::Lost => Some(format!("You lose! The word was {}.", self.word.to_uppercase())),
+        }
+    }
+
+    /// A spoiler-free summary of the game to paste into a chat, like the original game's share button.
+    pub fn share(&self, theme: Theme) -> String {
+        let score = match self.state {
+            GameState::Won => self.guesses_used().to_string(),
+            _ => "X".to_string(),
+        };
+        let mut response = match self.puzzle {
+            Some(puzzle) => format!("Wordle {} {}/{}", puzzle, score, self.rows.len()),
+            None => format!("Wordle {}/{}", score, self.rows.len()),
+        };
+        if self.config.hard_mode {
+            response.push('*');
+        }
+        match self.hints.len() {
+            0 => {}
+            1 => response.push_str(" (1 hint)"),
+            n => response.push_str(&format!(" ({} hints)", n)),
+        }
+        response.push('\n');
+        for r in self.rows.iter().take(self.guesses_used()) {
+            response.push('\n');
+            for c in r {
+                response.push_str(theme.emoji(c));
+            }
+        }
+        response
+    }
+
+    pub fn slack(&self, theme: Theme) -> String {
         let mut response = "".to_string();
         for r in self.rows.iter() {
             for c in r {
@@ -187,31 +629,21 @@ impl Board {
                     Cell::Empty => {}
                 }
             }
-            response.push_str("\n");
+            response.push('\n');
             for c in r {
-                match c {
-                    Cell::Green(_) => {
-                        response.push_str("🟩 ");
-                    }
-                    Cell::Yellow(_) => {
-                        response.push_str("🟨 ");
-                    }
-                    Cell::Gray(_) => {
-                        response.push_str("⬜️ ");
-                    }
-                    Cell::Empty => {
-                        response.push_str("⬛️ ");
-                    }
-                }
+                response.push_str(theme.emoji(c));
+                response.push(' ');
             }
-            response.push_str("\n");
+            response.push('\n');
         }
-        return response;
+        response.push('\n');
+        response.push_str(&self.keyboard.slack(theme));
+        response
     }
 
     // TODO: use a trait here instead
     #[cfg(not(target_arch = "wasm32"))]
-    pub fn print(&self) -> Result<(), Error> {
+    pub fn print(&self, theme: Theme) -> Result<(), Error> {
         terminal::enable_raw_mode().expect("Failed to enter raw mode");
         queue!(
             io::stdout(),
@@ -230,47 +662,26 @@ impl Board {
                 cursor::MoveTo(0, idx.try_into().unwrap())
             )?;
             for c in r {
-                match c {
-                    Cell::Green(value) => {
-                        queue!(
-                            io::stdout(),
-                            style::SetBackgroundColor(Color::Green),
-                            style::SetForegroundColor(Color::Black),
-                            style::Print(format!("  {}  ", value))
-                        )?;
-                    }
-                    Cell::Yellow(value) => {
-                        queue!(
-                            io::stdout(),
-                            style::SetBackgroundColor(Color::Yellow),
-                            style::SetForegroundColor(Color::Black),
-                            style::Print(format!("  {}  ", value))
-                        )?;
-                    }
-                    Cell::Gray(value) => {
-                        queue!(
-                            io::stdout(),
-                            style::SetBackgroundColor(Color::Grey),
-                            style::SetForegroundColor(Color::Black),
-                            style::Print(format!("  {}  ", value))
-                        )?;
-                    }
-                    Cell::Empty => {
-                        queue!(
-                            io::stdout(),
-                            style::SetBackgroundColor(Color::DarkGrey),
-                            style::Print("     ")
-                        )?;
-                    }
-                }
+                let (background, foreground) = theme.terminal(c);
+                let text = match c {
+                    Cell::Empty => "     ".to_string(),
+                    c => format!("  {}  ", c),
+                };
+                queue!(
+                    io::stdout(),
+                    style::SetBackgroundColor(background),
+                    style::SetForegroundColor(foreground),
+                    style::Print(text)
+                )?;
             }
         }
         queue!(io::stdout(), style::ResetColor).expect("Failed to reset color");
+        self.keyboard.print(self.rows.len() as u16 + 1, theme)?;
 
         // END CODE
         io::stdout().flush()?;
         terminal::disable_raw_mode()?;
-        return Ok(());
+        Ok(())
     }
 }
 
@@ -280,7 +691,7 @@ impl fmt::Debug for Board {
             for c in r {
                 write!(f, "{:?} | ", c)?;
             }
-            write!(f, "\n")?;
+            writeln!(f)?;
         }
         Ok(())
     }
@@ -290,11 +701,47 @@ impl fmt::Debug for Board {
 mod tests {
     use crate::board::Board;
     use crate::board::Cell;
+    use crate::board::{BoardConfig, GameState, GuessError, GuessOutcome};
+    use crate::dictionary::{DictionaryLike, WebDictionary};
+    use crate::hint::Hint;
+    use crate::keyboard::LetterStatus;
+    use crate::theme::Theme;
+    use chrono::NaiveDate;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    struct AnyWord;
+
+    impl DictionaryLike for AnyWord {
+        fn is_allowed_guess(&self, _word: &str) -> bool {
+            true
+        }
+
+        fn answers(&self, _length: usize) -> &[String] {
+            &[]
+        }
+    }
+
+    struct NoWords;
+
+    impl DictionaryLike for NoWords {
+        fn is_allowed_guess(&self, _word: &str) -> bool {
+            false
+        }
+
+        fn answers(&self, _length: usize) -> &[String] {
+            &[]
+        }
+    }
+
+    fn board(word: &str) -> Board {
+        Board::with_dictionary(word.to_string(), BoardConfig::default(), Box::new(AnyWord))
+    }
 
     #[test]
     fn green() {
-        let mut board = Board::new("rusty".to_string());
-        board.guess("rusty");
+        let mut board = board("rusty");
+        board.guess("rusty").unwrap();
         assert_eq!(
             board.rows[0],
             [
@@ -309,8 +756,8 @@ mod tests {
 
     #[test]
     fn fail_case() {
-        let mut board = Board::new("rusty".to_string());
-        board.guess("tests");
+        let mut board = board("rusty");
+        board.guess("tests").unwrap();
         assert_eq!(
             board.rows[0],
             [
@@ -325,8 +772,8 @@ mod tests {
 
     #[test]
     fn yellow() {
-        let mut board = Board::new("rusty".to_string());
-        board.guess("rutsy");
+        let mut board = board("rusty");
+        board.guess("rutsy").unwrap();
         assert_eq!(
             board.rows[0],
             [
@@ -341,8 +788,8 @@ mod tests {
 
     #[test]
     fn gray() {
-        let mut board = Board::new("rusty".to_string());
-        board.guess("abcde");
+        let mut board = board("rusty");
+        board.guess("abcde").unwrap();
         assert_eq!(
             board.rows[0],
             [
@@ -357,8 +804,8 @@ mod tests {
 
     #[test]
     fn already_used_green() {
-        let mut board = Board::new("rusty".to_string());
-        board.guess("ruuty");
+        let mut board = board("rusty");
+        board.guess("ruuty").unwrap();
         assert_eq!(
             board.rows[0],
             [
@@ -373,8 +820,8 @@ mod tests {
 
     #[test]
     fn already_used_green_first() {
-        let mut board = Board::new("rusty".to_string());
-        board.guess("uusty");
+        let mut board = board("rusty");
+        board.guess("uusty").unwrap();
         assert_eq!(
             board.rows[0],
             [
@@ -389,9 +836,9 @@ mod tests {
 
     #[test]
     fn guess_the_same_thing() {
-        let mut board = Board::new("rusty".to_string());
-        board.guess("rutsy");
-        board.guess("rutsy");
+        let mut board = board("rusty");
+        board.guess("rutsy").unwrap();
+        board.guess("rutsy").unwrap();
         assert_eq!(
             board.rows[0],
             [
@@ -416,27 +863,400 @@ mod tests {
 
     #[test]
     fn has_won() {
-        let mut board = Board::new("rusty".to_string());
-        board.guess("rusty");
-        assert_eq!(board.has_won(), true);
+        let mut board = board("rusty");
+        board.guess("rusty").unwrap();
+        assert!(board.has_won());
     }
 
     #[test]
     fn has_won_2nd() {
-        let mut board = Board::new("rusty".to_string());
-        board.guess("rogue");
-        assert_eq!(board.has_won(), false);
-        board.guess("rusty");
-        assert_eq!(board.has_won(), true);
+        let mut board = board("rusty");
+        board.guess("rogue").unwrap();
+        assert!(!board.has_won());
+        board.guess("rusty").unwrap();
+        assert!(board.has_won());
     }
 
     #[test]
     fn has_won_multiple() {
-        let mut board = Board::new("rusty".to_string());
-        board.guess("rogue");
-        assert_eq!(board.has_won(), false);
-        board.guess("rusty");
-        board.guess("rusty");
-        assert_eq!(board.has_won(), true);
+        let mut board = board("rusty");
+        board.guess("rogue").unwrap();
+        assert!(!board.has_won());
+        board.guess("rusty").unwrap();
+        assert_eq!(board.guess("rusty"), Err(GuessError::GameOver));
+        assert!(board.has_won());
+    }
+
+    #[test]
+    fn guess_outcomes() {
+        let mut board = board("rusty");
+        assert_eq!(board.guess("rogue"), Ok(GuessOutcome::Incorrect));
+        assert_eq!(board.guess("RUSTY"), Ok(GuessOutcome::Solved));
+    }
+
+    #[test]
+    fn out_of_guesses() {
+        let mut board = board("rusty");
+        for _ in 0..5 {
+            assert_eq!(board.guess("rogue"), Ok(GuessOutcome::Incorrect));
+        }
+        assert_eq!(board.guess("rogue"), Ok(GuessOutcome::OutOfGuesses));
+        assert_eq!(board.guess("rusty"), Err(GuessError::GameOver));
+    }
+
+    #[test]
+    fn rejects_bad_guesses() {
+        let mut board = board("rusty");
+        assert_eq!(
+            board.guess("rust"),
+            Err(GuessError::TooShort { expected: 5, actual: 4 })
+        );
+        assert_eq!(
+            board.guess("rustier"),
+            Err(GuessError::TooLong { expected: 5, actual: 7 })
+        );
+        assert_eq!(
+            board.guess("ru5ty"),
+            Err(GuessError::NonAlphabetic("ru5ty".to_string()))
+        );
+        assert_eq!(board.rows[0], [Cell::Empty; 5]);
+    }
+
+    #[test]
+    fn rejects_unknown_words() {
+        let mut board = Board::with_dictionary("rusty".to_string(), BoardConfig::default(), Box::new(NoWords));
+        assert_eq!(
+            board.guess("rusty"),
+            Err(GuessError::NotInDictionary("rusty".to_string()))
+        );
+        assert_eq!(board.rows[0], [Cell::Empty; 5]);
+    }
+
+    #[test]
+    fn give_up() {
+        let mut board = board("rusty");
+        board.guess("trust").unwrap();
+        assert!(board.give_up());
+        assert_eq!(board.state(), GameState::Lost);
+        assert_eq!(board.reveal_answer(), Some("rusty"));
+        assert!(board.finished_at().is_some());
+        assert!(!board.give_up());
+        assert_eq!(board.guess("rusty"), Err(GuessError::GameOver));
+    }
+
+    #[test]
+    fn state_transitions() {
+        let mut board = board("rusty");
+        assert_eq!(board.state(), GameState::InProgress);
+        assert_eq!(board.remaining_guesses(), 6);
+        assert_eq!(board.reveal_answer(), None);
+        board.guess("rogue").unwrap();
+        assert_eq!(board.remaining_guesses(), 5);
+        assert_eq!(board.result_message(), None);
+        board.guess("rusty").unwrap();
+        assert_eq!(board.state(), GameState::Won);
+        assert_eq!(board.remaining_guesses(), 4);
+        assert_eq!(board.reveal_answer(), Some("rusty"));
+        assert_eq!(board.result_message(), Some("You won in 2/6!".to_string()));
+    }
+
+    #[test]
+    fn lost_game() {
+        let mut board = board("rusty");
+        for _ in 0..6 {
+            board.guess("rogue").unwrap();
+        }
+        assert_eq!(board.state(), GameState::Lost);
+        assert_eq!(board.remaining_guesses(), 0);
+        assert_eq!(board.reveal_answer(), Some("rusty"));
+        assert_eq!(
+            board.result_message(),
+            Some("You lose! The word was RUSTY.".to_string())
+        );
+    }
+
+    #[test]
+    fn custom_config() {
+        let config = BoardConfig {
+            word_length: 7,
+            max_guesses: 2,
+            ..BoardConfig::default()
+        };
+        let mut board = Board::with_dictionary("rustier".to_string(), config, Box::new(AnyWord));
+        assert_eq!(board.rows.len(), 2);
+        assert_eq!(board.remaining_guesses(), 2);
+        assert_eq!(
+            board.guess("rusty"),
+            Err(GuessError::TooShort { expected: 7, actual: 5 })
+        );
+        board.guess("trusses").unwrap();
+        assert_eq!(
+            board.rows[0],
+            [
+                Cell::Yellow('t'),
+                Cell::Yellow('r'),
+                Cell::Yellow('u'),
+                Cell::Yellow('s'),
+                Cell::Gray('s'),
+                Cell::Green('e'),
+                Cell::Gray('s')
+            ]
+        );
+        assert_eq!(board.guess("rustier"), Ok(GuessOutcome::Solved));
+        assert_eq!(board.result_message(), Some("You won in 2/2!".to_string()));
+    }
+
+    #[test]
+    fn four_letters() {
+        let config = BoardConfig {
+            word_length: 4,
+            ..BoardConfig::default()
+        };
+        let mut board = Board::with_dictionary("rust".to_string(), config, Box::new(AnyWord));
+        assert_eq!(
+            board.guess("rusty"),
+            Err(GuessError::TooLong { expected: 4, actual: 5 })
+        );
+        board.guess("tsar").unwrap();
+        assert_eq!(
+            board.rows[0],
+            [Cell::Yellow('t'), Cell::Yellow('s'), Cell::Gray('a'), Cell::Yellow('r')]
+        );
+    }
+
+    fn hard_board(word: &str) -> Board {
+        let config = BoardConfig {
+            hard_mode: true,
+            ..BoardConfig::default()
+        };
+        Board::with_dictionary(word.to_string(), config, Box::new(AnyWord))
+    }
+
+    #[test]
+    fn hard_mode_keeps_greens() {
+        let mut board = hard_board("rusty");
+        board.guess("tests").unwrap();
+        assert_eq!(
+            board.guess("rogue"),
+            Err(GuessError::MustPlaceLetter {
+                position: 2,
+                letter: 's'
+            })
+        );
+        assert_eq!(board.guess("rogue").unwrap_err().to_string(), "3rd letter must be S");
+        assert_eq!(board.guesses_used(), 1);
+        assert_eq!(board.guess("rusty"), Ok(GuessOutcome::Solved));
+    }
+
+    #[test]
+    fn hard_mode_keeps_yellows() {
+        let mut board = hard_board("rusty");
+        board.guess("tramp").unwrap();
+        assert_eq!(board.guess("rogue"), Err(GuessError::MustContainLetter('t')));
+        assert_eq!(board.guess("rogue").unwrap_err().to_string(), "Guess must contain T");
+        board.guess("rants").unwrap();
+    }
+
+    #[test]
+    fn hard_mode_only_before_first_guess() {
+        let mut board = board("rusty");
+        board.guess("rogue").unwrap();
+        assert!(!board.set_hard_mode(true));
+        board.guess("tramp").unwrap();
+
+        let mut board = hard_board("rusty");
+        board.guess("rogue").unwrap();
+        assert!(board.set_hard_mode(false));
+        board.guess("tramp").unwrap();
+    }
+
+    #[test]
+    fn random_board() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let config = BoardConfig {
+            word_length: 6,
+            ..BoardConfig::default()
+        };
+        let mut board = Board::random(config, Box::new(WebDictionary::new()), &mut rng).unwrap();
+        assert_eq!(
+            board.guess("rusty"),
+            Err(GuessError::TooShort { expected: 6, actual: 5 })
+        );
+        assert!(Board::random(config, Box::new(AnyWord), &mut rng).is_none());
+    }
+
+    #[test]
+    fn daily_puzzle() {
+        let date = NaiveDate::from_ymd_opt(2022, 8, 5).unwrap();
+        let config = BoardConfig::default();
+        let board = Board::daily(date, config, Box::new(WebDictionary::new())).unwrap();
+        let again = Board::daily(date, config, Box::new(WebDictionary::new())).unwrap();
+        assert_eq!(board.puzzle_number(), Some(412));
+        assert_eq!(board.word, again.word);
+        let tomorrow = Board::daily(date.succ_opt().unwrap(), config, Box::new(WebDictionary::new())).unwrap();
+        assert_eq!(tomorrow.puzzle_number(), Some(413));
+        assert_ne!(board.word, tomorrow.word);
+        assert!(Board::daily(date, config, Box::new(AnyWord)).is_none());
+    }
+
+    #[test]
+    fn share() {
+        let mut board = board("rusty");
+        board.puzzle = Some(412);
+        board.guess("tramp").unwrap();
+        board.guess("rusty").unwrap();
+        assert_eq!(board.share(Theme::Standard), "Wordle 412 2/6\n\n🟨🟨⬜⬜⬜\n🟩🟩🟩🟩🟩");
+        assert_eq!(
+            board.share(Theme::HighContrast),
+            "Wordle 412 2/6\n\n🟦🟦⬜⬜⬜\n🟧🟧🟧🟧🟧"
+        );
+        assert!(board.share(Theme::Dark).ends_with("🟨🟨⬛⬛⬛\n🟩🟩🟩🟩🟩"));
+
+        let mut board = hard_board("rusty");
+        for _ in 0..6 {
+            board.guess("abcde").unwrap();
+        }
+        assert!(board.share(Theme::Standard).starts_with("Wordle X/6*\n\n⬜⬜⬜⬜⬜\n"));
+    }
+
+    #[test]
+    fn wasm_board_rejects_non_words() {
+        let mut board = Board::new_wasm("rusty".to_string(), BoardConfig::default());
+        assert_eq!(
+            board.guess("abcde"),
+            Err(GuessError::NotInDictionary("abcde".to_string()))
+        );
+        assert_eq!(board.guess("rogue"), Ok(GuessOutcome::Incorrect));
+    }
+
+    #[test]
+    fn escalating_hints() {
+        let mut board = Board::new_wasm("rusty".to_string(), BoardConfig::default());
+        board.guess("tramp").unwrap();
+        match board.hint() {
+            Some(Hint::RemainingAnswers(n)) => assert!(n > 1),
+            hint => panic!("unexpected hint {:?}", hint),
+        }
+        assert_eq!(board.hint(), Some(Hint::PresentLetter('u')));
+        assert_eq!(
+            board.hint(),
+            Some(Hint::CorrectPosition {
+                position: 0,
+                letter: 'r'
+            })
+        );
+        match board.hint() {
+            Some(Hint::SuggestedGuess(word)) => assert_eq!(word.len(), 5),
+            hint => panic!("unexpected hint {:?}", hint),
+        }
+        assert_eq!(board.hints().len(), 4);
+        assert_eq!(
+            Hint::CorrectPosition {
+                position: 0,
+                letter: 'r'
+            }
+            .to_string(),
+            "The 1st letter is R."
+        );
+
+        board.guess("rusty").unwrap();
+        assert_eq!(board.hint(), None);
+        assert!(board.share(Theme::Standard).starts_with("Wordle 2/6 (4 hints)\n"));
+    }
+
+    #[test]
+    fn keyboard_tracks_guesses() {
+        let mut board = board("rusty");
+        board.guess("tramp").unwrap();
+        assert_eq!(board.keyboard().status('t'), LetterStatus::Present);
+        board.guess("rusts").unwrap();
+        assert_eq!(board.keyboard().status('t'), LetterStatus::Correct);
+        assert_eq!(board.keyboard().status('s'), LetterStatus::Correct);
+        assert_eq!(board.keyboard().status('a'), LetterStatus::Absent);
+        assert_eq!(board.keyboard().status('m'), LetterStatus::Absent);
+        assert_eq!(board.keyboard().status('z'), LetterStatus::Unknown);
+    }
+
+    #[test]
+    fn saved_game_round_trip() {
+        let mut board = hard_board("rusty");
+        board.puzzle = Some(412);
+        board.guess("tramp").unwrap();
+        board.hint();
+        let saved = board.to_saved();
+        assert_eq!(saved.guesses, vec!["tramp"]);
+
+        let mut loaded = Board::from_saved(saved, Box::new(AnyWord)).unwrap();
+        assert_eq!(loaded.rows, board.rows);
+        assert_eq!(loaded.state(), GameState::InProgress);
+        assert_eq!(loaded.puzzle_number(), Some(412));
+        assert_eq!(loaded.hints(), board.hints());
+        assert_eq!(loaded.started_at(), board.started_at());
+        assert_eq!(loaded.keyboard().status('t'), LetterStatus::Present);
+        assert_eq!(loaded.guess("rogue"), Err(GuessError::MustContainLetter('t')));
+        assert_eq!(loaded.guess("rusty"), Ok(GuessOutcome::Solved));
+        assert!(loaded.finished_at().is_some());
+    }
+
+    #[test]
+    fn rejects_saves_that_dont_fit() {
+        let mut saved = board("rusty").to_saved();
+        saved.guesses = vec!["rust".to_string()];
+        assert!(Board::from_saved(saved, Box::new(AnyWord)).is_err());
+    }
+
+    #[test]
+    fn rejects_saves_whose_result_doesnt_match() {
+        let mut lost = board("rusty");
+        for _ in 0..6 {
+            lost.guess("tramp").unwrap();
+        }
+        let mut saved = lost.to_saved();
+        assert_eq!(saved.state, GameState::Lost);
+        saved.state = GameState::InProgress;
+        let e = Board::from_saved(saved, Box::new(AnyWord)).unwrap_err();
+        assert_eq!(e.kind(), std::io::ErrorKind::InvalidData);
+
+        let mut saved = board("rusty").to_saved();
+        saved.guesses = vec!["tramp".to_string()];
+        saved.state = GameState::Won;
+        assert!(Board::from_saved(saved, Box::new(AnyWord)).is_err());
+
+        let mut saved = board("rusty").to_saved();
+        saved.guesses = vec!["rusty".to_string(), "tramp".to_string()];
+        saved.state = GameState::Won;
+        assert!(Board::from_saved(saved, Box::new(AnyWord)).is_err());
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn save_and_load_a_game_given_up() {
+        let path = std::env::temp_dir().join(format!("wordle-give-up-{}.json", std::process::id()));
+        let mut board = board("rusty");
+        board.guess("tramp").unwrap();
+        assert!(board.give_up());
+        board.save(&path).unwrap();
+        let mut loaded = Board::load_with_dictionary(&path, Box::new(AnyWord)).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.state(), GameState::Lost);
+        assert_eq!(loaded.guesses(), vec!["tramp"]);
+        assert_eq!(loaded.remaining_guesses(), 5);
+        assert_eq!(loaded.finished_at(), board.finished_at());
+        assert!(!loaded.give_up());
+        assert!(loaded.guess("rusty").is_err());
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn save_and_load() {
+        let path = std::env::temp_dir().join(format!("wordle-save-{}.json", std::process::id()));
+        let mut board = board("rusty");
+        board.guess("rogue").unwrap();
+        board.save(&path).unwrap();
+        let loaded = Board::load_with_dictionary(&path, Box::new(AnyWord)).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.guesses(), vec!["rogue"]);
+        assert_eq!(loaded.remaining_guesses(), 5);
+        assert!(Board::load_with_dictionary(&path, Box::new(AnyWord)).is_err());
     }
 }
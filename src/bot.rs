@@ -0,0 +1,1072 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Mutex;
+
+use chrono::{DateTime, Local, Utc};
+use serde_json::Value;
+
+use crate::blocks;
+use crate::board::{Board, BoardConfig};
+use crate::command::{Command, HELP};
+use crate::config::SlackConfig;
+use crate::daily;
+use crate::events::{self, Response};
+use crate::schedule::{Schedule, Task};
+use crate::session::{SessionKey, SessionManager, SessionMode};
+use crate::signature;
+use crate::slack::{Message, SlackClient, SlackError};
+use crate::stats::GameRecord;
+use crate::theme::Theme;
+
+/// The Slack bot: checks requests, plays the games they're about and posts the replies.
+pub struct Bot {
+    settings: SlackConfig,
+    sessions: Mutex<SessionManager>,
+    slack: Box<dyn SlackClient>,
+    save_path: Option<PathBuf>,
+    /// Replies waiting to be posted, when they're posted after the request is answered.
+    outbox: Option<(Sender<Job>, Mutex<Receiver<Job>>)>,
+}
+
+/// Posting that's been put off until the request has been answered.
+type Job = Box<dyn FnOnce(&Bot) + Send>;
+
+/// What the bot says back. Anything that changes a game is posted for the channel to see, the
+/// rest only to whoever asked.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reply {
+    pub message: Message,
+    pub public: bool,
+    /// The board after the change, to keep the top of the game's thread up to date.
+    pub board: Option<Message>,
+    pub game_over: bool,
+    /// Whether it belongs in the game's thread, which the daily puzzle has nothing to do with.
+    pub threaded: bool,
+}
+
+impl Reply {
+    fn public<S: Into<String>>(text: S) -> Self {
+        Reply {
+            message: Message::new(text),
+            public: true,
+            board: None,
+            game_over: false,
+            threaded: true,
+        }
+    }
+
+    fn private<S: Into<String>>(text: S) -> Self {
+        Reply {
+            public: false,
+            ..Reply::public(text)
+        }
+    }
+
+    fn with_blocks(mut self, blocks: Value) -> Self {
+        self.message = self.message.with_blocks(blocks);
+        self
+    }
+
+    fn unthreaded(mut self) -> Self {
+        self.threaded = false;
+        self
+    }
+
+    fn with_board(mut self, board: &Board, theme: Theme) -> Self {
+        self.board = Some(Message::new(board.slack(theme)).with_blocks(blocks::board(board, theme)));
+        self.game_over = board.is_over();
+        self
+    }
+}
+
+impl Bot {
+    pub fn new(settings: SlackConfig, sessions: SessionManager, slack: Box<dyn SlackClient>) -> Self {
+        Bot {
+            settings,
+            sessions: Mutex::new(sessions),
+            slack,
+            save_path: None,
+            outbox: None,
+        }
+    }
+
+    /// Answers requests before posting the replies, which `post_replies` then does in the order
+    /// they came in. Slack gives up on a request after three seconds and sends it again, and a
+    /// rate limited post can take longer than that.
+    pub fn posting_later(mut self) -> Self {
+        let (jobs, queue) = mpsc::channel();
+        self.outbox = Some((jobs, Mutex::new(queue)));
+        self
+    }
+
+    /// Posts the replies `posting_later` put off, for as long as the bot is around. Run it on a
+    /// thread of its own.
+    pub fn post_replies(&self) {
+        if let Some((_, queue)) = &self.outbox {
+            for job in queue.lock().unwrap().iter() {
+                job(self);
+            }
+        }
+    }
+
+    fn later<F: FnOnce(&Bot) + Send + 'static>(&self, job: F) {
+        match &self.outbox {
+            Some((jobs, _)) => jobs
+                .send(Box::new(job))
+                .expect("the bot's outbox is open while it's around"),
+            None => job(self),
+        }
+    }
+
+    /// Sends `reply` once the request has been answered.
+    fn reply(&self, team: &str, channel: &str, user: &str, reply: Reply) {
+        let (team, channel, user) = (team.to_string(), channel.to_string(), user.to_string());
+        self.later(move |bot| bot.send(&team, &channel, &user, reply));
+    }
+
+    /// Saves every game to `path` after each change.
+    pub fn saving_to<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.save_path = Some(path.into());
+        self
+    }
+
+    /// Saving is best effort, same as for a single game.
+    fn save(&self, sessions: &SessionManager) {
+        if let Some(path) = &self.save_path {
+            if let Err(e) = sessions.save(path) {
+                eprintln!("Couldn't save the games to {}: {}", path.display(), e);
+            }
+        }
+    }
+
+    /// Posts a reply in `channel`. Each game lives in a thread: the first public reply about it is
+    /// posted to the channel and the rest go in its thread, with the first message edited to show
+    /// the board as it is now. The result is broadcast back to the channel.
+    fn send(&self, team: &str, channel: &str, user: &str, reply: Reply) {
+        let key = self.sessions.lock().unwrap().key(team, channel, user);
+        let thread = self.sessions.lock().unwrap().thread(&key).map(String::from);
+        let mut message = reply.message;
+        if reply.threaded {
+            message.thread_ts = thread.clone();
+        }
+        if !reply.public {
+            return log_error(channel, self.slack.post_ephemeral(channel, user, &message));
+        }
+
+        self.mention(user, &mut message);
+        let thread = match thread {
+            Some(thread) => thread,
+            None => return self.start_thread(&key, channel, &message),
+        };
+        message.reply_broadcast = reply.game_over;
+        log_error(channel, self.slack.post_message(channel, &message).map(|_| ()));
+        if let Some(mut board) = reply.board {
+            self.mention(user, &mut board);
+            log_error(channel, self.slack.update_message(channel, &thread, &board));
+        }
+    }
+
+    /// Posts `message` to the channel, for the rest of the game to be played under.
+    fn start_thread(&self, key: &SessionKey, channel: &str, message: &Message) {
+        match self.slack.post_message(channel, message) {
+            Ok(posted) => {
+                let mut sessions = self.sessions.lock().unwrap();
+                sessions.set_thread(key, posted.ts);
+                self.save(&sessions);
+            }
+            Err(e) => eprintln!("Couldn't post to {}: {}", channel, e),
+        }
+    }
+
+    /// In user mode, public replies say whose game they're about.
+    fn mention(&self, user: &str, message: &mut Message) {
+        if self.settings.session_mode == SessionMode::User {
+            message.text = format!("<@{}> {}", user, message.text);
+            if let Some(Value::Array(blocks)) = &mut message.blocks {
+                blocks.insert(0, blocks::text(&format!("<@{}>'s game", user))[0].clone());
+            }
+        }
+    }
+
+    /// Handles a request to the Events API endpoint.
+    pub fn handle_event(&self, headers: &HashMap<String, String>, body: Option<&str>, now: DateTime<Utc>) -> Response {
+        // only listen to Slack, and don't let a bad request take the server down
+        let v = match events::parse(&self.settings.signing_secret, headers, body, now.timestamp()) {
+            Ok(v) => v,
+            Err(response) => return response,
+        };
+        if v["type"] == "url_verification" {
+            return Response::ok(v["challenge"].as_str().unwrap_or_default());
+        }
+        // Slack sends an event again when it didn't hear back in time, but it's been seen already
+        if signature::header(headers, events::RETRY_HEADER).is_some() {
+            return Response::ok("");
+        }
+        self.handle_message(&v["team_id"], &v["event"], now);
+        Response::ok("")
+    }
+
+    /// Handles a `/wordle` slash command, which Slack sends form encoded.
+    pub fn handle_command(
+        &self,
+        headers: &HashMap<String, String>,
+        body: Option<&str>,
+        now: DateTime<Utc>,
+    ) -> Response {
+        let body = match events::authenticate(&self.settings.signing_secret, headers, body, now.timestamp()) {
+            Ok(body) => body,
+            Err(response) => return response,
+        };
+        let form: HashMap<String, String> = match serde_urlencoded::from_str(body) {
+            Ok(form) => form,
+            Err(e) => return Response::bad_request(format!("invalid form: {}", e)),
+        };
+        let field = |name: &str| form.get(name).map(String::as_str);
+        let (team, channel, user) = match (field("team_id"), field("channel_id"), field("user_id")) {
+            (Some(team), Some(channel), Some(user)) => (team, channel, user),
+            _ => return Response::bad_request("missing team_id, channel_id or user_id"),
+        };
+
+        let reply = match field("text").unwrap_or_default().parse::<Command>() {
+            Ok(command) => self.run(team, channel, user, command, now),
+            Err(e) => Reply::private(e.to_string()),
+        };
+        self.reply(team, channel, user, reply);
+        Response::ok("")
+    }
+
+    /// Handles a button press on one of the bot's messages. Slack sends these form encoded, with
+    /// the JSON in a `payload` field.
+    pub fn handle_interaction(
+        &self,
+        headers: &HashMap<String, String>,
+        body: Option<&str>,
+        now: DateTime<Utc>,
+    ) -> Response {
+        let body = match events::authenticate(&self.settings.signing_secret, headers, body, now.timestamp()) {
+            Ok(body) => body,
+            Err(response) => return response,
+        };
+        let form: HashMap<String, String> = match serde_urlencoded::from_str(body) {
+            Ok(form) => form,
+            Err(e) => return Response::bad_request(format!("invalid form: {}", e)),
+        };
+        let payload: Value = match form.get("payload").map(|p| serde_json::from_str(p)) {
+            Some(Ok(payload)) => payload,
+            Some(Err(e)) => return Response::bad_request(format!("invalid payload: {}", e)),
+            None => return Response::bad_request("missing payload"),
+        };
+        if payload["type"] != "block_actions" {
+            return Response::ok("");
+        }
+
+        let team = payload["team"]["id"].as_str().unwrap_or_default();
+        let user = match payload["user"]["id"].as_str() {
+            Some(user) => user,
+            None => return Response::bad_request("missing user"),
+        };
+        let channel = payload["channel"]["id"]
+            .as_str()
+            .unwrap_or(&self.settings.default_channel);
+        let actions = payload["actions"].as_array().map(Vec::as_slice).unwrap_or_default();
+        for action in actions {
+            let command = match action["action_id"].as_str() {
+                Some(blocks::NEW_GAME_ACTION) => Command::New(None),
+                Some(blocks::HINT_ACTION) => Command::Hint,
+                _ => continue,
+            };
+            let reply = self.run(team, channel, user, command, now);
+            self.reply(team, channel, user, reply);
+        }
+        Response::ok("")
+    }
+
+    fn handle_message(&self, team: &Value, event: &Value, now: DateTime<Utc>) {
+        // don't respond to the bot's own messages, or to edits and other subtypes
+        if event["user"] == self.settings.bot_user_id.as_str() || event.get("bot_id").is_some() {
+            return;
+        }
+        if event["type"] != "message" || event.get("subtype").is_some() {
+            return;
+        }
+        let input = match event["text"].as_str() {
+            Some(input) => input.trim(),
+            None => return,
+        };
+        let channel = event["channel"].as_str().unwrap_or(&self.settings.default_channel);
+        let team = team.as_str().unwrap_or_default();
+        let user = event["user"].as_str().unwrap_or_default();
+        if event["channel_type"] == "im" {
+            let reply = self.direct_message(team, channel, user, input, now);
+            let channel = channel.to_string();
+            return self
+                .later(move |bot| log_error(&channel, bot.slack.post_message(&channel, &reply.message).map(|_| ())));
+        }
+        // threads other than the game's are people talking among themselves
+        if let Some(thread) = event["thread_ts"].as_str() {
+            let sessions = self.sessions.lock().unwrap();
+            if sessions.thread(&sessions.key(team, channel, user)) != Some(thread) {
+                return;
+            }
+        }
+
+        let reply = if let Some(hard_mode) = parse_hard_mode(input) {
+            self.set_hard_mode(team, channel, user, hard_mode, now)
+        } else {
+            match input.parse::<Command>() {
+                Ok(command) => self.run(team, channel, user, command, now),
+                Err(e) => Reply::private(e.to_string()),
+            }
+        };
+        self.reply(team, channel, user, reply);
+    }
+
+    /// Nobody else can see a direct message, so that's where the daily puzzle is played.
+    fn direct_message(&self, team: &str, channel: &str, user: &str, input: &str, now: DateTime<Utc>) -> Reply {
+        let command = match input.parse::<Command>() {
+            Ok(Command::Guess(word)) => Command::Daily(Some(word)),
+            Ok(Command::Board) => Command::Daily(None),
+            Ok(command @ (Command::Daily(_) | Command::Stats | Command::Theme(_) | Command::Help)) => command,
+            Ok(_) => return Reply::private("Here you can only play the daily puzzle. Send me a guess!"),
+            Err(e) => return Reply::private(e.to_string()),
+        };
+        self.run(team, channel, user, command, now)
+    }
+
+    fn set_hard_mode(&self, team: &str, channel: &str, user: &str, hard_mode: bool, now: DateTime<Utc>) -> Reply {
+        let mut sessions = self.sessions.lock().unwrap();
+        let key = sessions.key(team, channel, user);
+        let theme = sessions.preferences(user).theme;
+        let board = match sessions.game(&key, now) {
+            Some(board) => board,
+            None => return Reply::private("There are no words to play with."),
+        };
+        let reply = if board.set_hard_mode(hard_mode) {
+            Reply::public(format!("Hard mode is {}.", if hard_mode { "on" } else { "off" })).with_board(board, theme)
+        } else {
+            Reply::private("Hard mode can only be turned on before the first guess.")
+        };
+        self.save(&sessions);
+        reply
+    }
+
+    /// Plays `command` in the game `user` is part of.
+    pub fn run(&self, team: &str, channel: &str, user: &str, command: Command, now: DateTime<Utc>) -> Reply {
+        let mut sessions = self.sessions.lock().unwrap();
+        sessions.expire(now);
+        let key = sessions.key(team, channel, user);
+        let theme = sessions.preferences(user).theme;
+        let reply = match command {
+            Command::Help => return Reply::private(HELP),
+            Command::Stats => return Reply::private(stats(&sessions, &key, user, theme)),
+            Command::Theme(None) => {
+                return Reply::private(format!(
+                    "Your boards are in the {} theme. Change it with `/wordle theme <name>`, choosing from {}.",
+                    theme,
+                    Theme::names()
+                ))
+            }
+            Command::Theme(Some(theme)) => {
+                sessions.set_theme(user, theme);
+                Reply::private(format!("Your boards will be in the {} theme from now on.", theme))
+            }
+            Command::New(length) => new_game(&mut sessions, &key, length, theme, now),
+            Command::Daily(guess) => daily(&mut sessions, user, guess, theme, now),
+            command => play(&mut sessions, &key, command, theme, now),
+        };
+        self.save(&sessions);
+        reply
+    }
+
+    /// Announces the daily puzzle or posts its results in the default channel.
+    pub fn run_task(&self, task: Task) {
+        let message = match task {
+            Task::Announce(date) => daily::puzzle_number(date).map(|puzzle| announcement(puzzle, self.settings.daily)),
+            Task::Digest(date) => {
+                daily::puzzle_number(date).map(|puzzle| digest(&self.sessions.lock().unwrap(), puzzle))
+            }
+        };
+        match message {
+            Some(message) => {
+                let channel = &self.settings.default_channel;
+                log_error(
+                    channel,
+                    self.slack.post_message(channel, &Message::new(message)).map(|_| ()),
+                );
+            }
+            None => eprintln!("There's no daily puzzle for {:?}", task),
+        }
+    }
+}
+
+fn new_game(
+    sessions: &mut SessionManager,
+    key: &SessionKey,
+    length: Option<usize>,
+    theme: Theme,
+    now: DateTime<Utc>,
+) -> Reply {
+    let config = BoardConfig {
+        word_length: length.unwrap_or(sessions.config().word_length),
+        ..sessions.config()
+    };
+    match sessions.start(key, config, now) {
+        Some(board) => Reply::public(format!(
+            "New game! Guess the {} letter word in {} tries.",
+            config.word_length,
+            board.remaining_guesses()
+        ))
+        .with_blocks(blocks::board(board, theme)),
+        None => Reply::private(format!(
+            "There are no {} letter words to play with.",
+            config.word_length
+        )),
+    }
+}
+
+/// Commands about the game in progress, which get a new one started if the last one is over.
+fn play(sessions: &mut SessionManager, key: &SessionKey, command: Command, theme: Theme, now: DateTime<Utc>) -> Reply {
+    let board = match sessions.game(key, now) {
+        Some(board) => board,
+        None => return Reply::private("There are no words to play with."),
+    };
+    let reply = match command {
+        Command::Guess(word) => match board.guess(&word) {
+            Ok(_) => {
+                let mut message = board.slack(theme);
+                if let Some(result) = board.result_message() {
+                    message.push_str(&format!("{}\n\n{}", result, board.share(theme)));
+                }
+                Reply::public(message).with_blocks(blocks::board(board, theme))
+            }
+            Err(e) => Reply::private(e.to_string()),
+        },
+        Command::Board => Reply::private(board.slack(theme)).with_blocks(blocks::board(board, theme)),
+        Command::Hint => match board.hint() {
+            Some(hint) => Reply::public(hint.to_string()).with_blocks(blocks::text(&hint.to_string())),
+            None => Reply::private("The game is over, no more hints!"),
+        },
+        Command::GiveUp => {
+            board.give_up();
+            let answer = board.reveal_answer().unwrap_or_default().to_uppercase();
+            Reply::public(format!(
+                "You gave up! The word was {}.\n\n{}",
+                answer,
+                board.share(theme)
+            ))
+            .with_blocks(blocks::board(board, theme))
+        }
+        Command::New(_) | Command::Daily(_) | Command::Stats | Command::Theme(_) | Command::Help => {
+            unreachable!("handled by Bot::run")
+        }
+    };
+    let reply = if reply.public {
+        reply.with_board(board, theme)
+    } else {
+        reply
+    };
+    if let Some(record) = GameRecord::from_board(board) {
+        sessions.record(key, record.won);
+        // a channel's game is everyone's, so it only counts towards the channel's tally
+        if let Some(user) = &key.user {
+            sessions.record_game(user, record);
+        }
+    }
+    reply
+}
+
+/// Today's puzzle, played privately so nobody spoils it for anyone else.
+fn daily(sessions: &mut SessionManager, user: &str, guess: Option<String>, theme: Theme, now: DateTime<Utc>) -> Reply {
+    let board = match sessions.daily_game(user, now.with_timezone(&Local).date_naive()) {
+        Some(board) => board,
+        None => return Reply::private("There's no daily puzzle today.").unthreaded(),
+    };
+    let word = match guess {
+        Some(word) => word,
+        None => {
+            return Reply::private(board.slack(theme))
+                .with_blocks(blocks::daily(board, theme))
+                .unthreaded()
+        }
+    };
+    if let Err(e) = board.guess(&word) {
+        return Reply::private(e.to_string()).unthreaded();
+    }
+    let mut message = board.slack(theme);
+    if let Some(result) = board.result_message() {
+        message.push_str(&format!("{}\n\n{}", result, board.share(theme)));
+    }
+    let reply = Reply::private(message)
+        .with_blocks(blocks::daily(board, theme))
+        .unthreaded();
+    if let Some(record) = GameRecord::from_board(board) {
+        sessions.record_game(user, record);
+    }
+    reply
+}
+
+fn announcement(puzzle: u32, schedule: Option<Schedule>) -> String {
+    let mut message = format!(
+        "Wordle {} is ready! Play it with `/wordle daily <word>`, or send me your guesses in a DM.",
+        puzzle
+    );
+    if let Some(schedule) = schedule {
+        message.push_str(&format!(" Results at {}.", schedule.digest_at.format("%H:%M")));
+    }
+    message
+}
+
+/// Everyone's spoiler-free grid for `puzzle`, best first, with their streaks.
+fn digest(sessions: &SessionManager, puzzle: u32) -> String {
+    let mut games = sessions.daily_games(puzzle);
+    if games.is_empty() {
+        return format!("Nobody played Wordle {}.", puzzle);
+    }
+    games.sort_by_key(|(_, board)| (!board.is_over(), !board.has_won(), board.guesses_used()));
+    let results: Vec<String> = games
+        .iter()
+        .map(|(user, board)| {
+            if board.is_over() {
+                let streak = sessions.stats(user).daily_streak(puzzle);
+                let theme = sessions.preferences(user).theme;
+                format!("<@{}> · streak {}\n{}", user, streak, board.share(theme))
+            } else {
+                format!(
+                    "<@{}> is still playing, {}/{} so far",
+                    user,
+                    board.guesses_used(),
+                    board.rows.len()
+                )
+            }
+        })
+        .collect();
+    format!("*Wordle {} results*\n\n{}", puzzle, results.join("\n\n"))
+}
+
+fn log_error<T>(channel: &str, result: Result<T, SlackError>) {
+    if let Err(e) = result {
+        eprintln!("Couldn't post to {}: {}", channel, e);
+    }
+}
+
+/// The player's own record, and in channel mode how the channel's shared games have gone.
+fn stats(sessions: &SessionManager, key: &SessionKey, user: &str, theme: Theme) -> String {
+    let summary = sessions.stats(user).summary();
+    let mut message = if summary.played == 0 {
+        "You haven't finished any games of your own yet.".to_string()
+    } else {
+        summary.slack(theme)
+    };
+    let tally = sessions.tally(key);
+    if sessions.mode() == SessionMode::Channel && tally.played > 0 {
+        message.push_str(&format!(
+            "\n\nThis channel has played {}, won {} ({}%).",
+            tally.played,
+            tally.won,
+            tally.won * 100 / tally.played
+        ));
+    }
+    message
+}
+
+/// "hard mode on" / "hard mode off", which can't be mistaken for a guess because of the spaces.
+fn parse_hard_mode(input: &str) -> Option<bool> {
+    match input.trim().to_lowercase().as_str() {
+        "hard mode on" => Some(true),
+        "hard mode off" => Some(false),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::collections::HashMap;
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Instant;
+
+    use chrono::{DateTime, Duration, Local, TimeZone, Utc};
+    use serde_json::json;
+
+    use crate::blocks;
+    use crate::board::BoardConfig;
+    use crate::bot::Bot;
+    use crate::config::{SlackConfig, DEFAULT_API_URL};
+    use crate::daily;
+    use crate::dictionary::{DictionaryLike, WebDictionary};
+    use crate::events::RETRY_HEADER;
+    use crate::schedule::Task;
+    use crate::session::{SessionManager, SessionMode};
+    use crate::signature::tests::{Fixture, MESSAGE_EVENT, URL_VERIFICATION};
+    use crate::signature::{sign, SIGNATURE_HEADER, TIMESTAMP_HEADER};
+    use crate::slack::{FakeSlack, ReqwestClient, SlackClient};
+
+    pub(crate) const SECRET: &str = "e3b8a7c1f04d4b8f9a6d2c5e7f1a0b93";
+
+    pub(crate) fn settings(mode: SessionMode) -> SlackConfig {
+        SlackConfig {
+            token: "xoxb-1234".to_string(),
+            signing_secret: SECRET.to_string(),
+            bot_user_id: "UBOT".to_string(),
+            default_channel: "rust-wordle-bot".to_string(),
+            listen_address: "127.0.0.1:3000".parse().unwrap(),
+            api_url: DEFAULT_API_URL.to_string(),
+            session_mode: mode,
+            daily: None,
+        }
+    }
+
+    /// A bot with a fake Slack, so tests can read what it posted.
+    pub(crate) fn bot(mode: SessionMode) -> (Bot, Arc<FakeSlack>) {
+        let slack = Arc::new(FakeSlack::new());
+        (bot_with(mode, Box::new(Arc::clone(&slack))), slack)
+    }
+
+    pub(crate) fn bot_with(mode: SessionMode, slack: Box<dyn SlackClient>) -> Bot {
+        let dictionary = Arc::new(WebDictionary::new());
+        let sessions = SessionManager::new(mode, BoardConfig::default(), Duration::hours(24), dictionary);
+        Bot::new(settings(mode), sessions, slack)
+    }
+
+    pub(crate) fn now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2022, 8, 5, 12, 0, 0).unwrap()
+    }
+
+    /// Signs `body` the way Slack would at `now`.
+    pub(crate) fn signed(body: &str, now: DateTime<Utc>) -> HashMap<String, String> {
+        let timestamp = now.timestamp().to_string();
+        HashMap::from([
+            (SIGNATURE_HEADER.to_string(), sign(SECRET, &timestamp, body)),
+            (TIMESTAMP_HEADER.to_string(), timestamp),
+        ])
+    }
+
+    fn message(user: &str, channel: &str, text: &str) -> String {
+        json!({
+            "team_id": "T061EG9RZ",
+            "type": "event_callback",
+            "event": { "type": "message", "channel": channel, "user": user, "text": text },
+        })
+        .to_string()
+    }
+
+    fn send(bot: &Bot, user: &str, channel: &str, text: &str) -> u16 {
+        let body = message(user, channel, text);
+        bot.handle_event(&signed(&body, now()), Some(&body), now()).status
+    }
+
+    #[test]
+    fn replies_to_recorded_event() {
+        let (bot, slack) = bot(SessionMode::Channel);
+        let fixture = Fixture::load(MESSAGE_EVENT);
+        let now = Utc.timestamp_opt(fixture.now(), 0).unwrap();
+        let response = bot.handle_event(&fixture.headers(), Some(&fixture.body), now);
+        assert_eq!(response.status, 200);
+
+        let sent = slack.take();
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].channel, "C2147483705");
+        assert!(sent[0].text.contains("   C   R   A   N   E"), "{}", sent[0].text);
+    }
+
+    #[test]
+    fn answers_url_verification() {
+        let (bot, slack) = bot(SessionMode::Channel);
+        let fixture = Fixture::load(URL_VERIFICATION);
+        let now = Utc.timestamp_opt(fixture.now(), 0).unwrap();
+        let response = bot.handle_event(&fixture.headers(), Some(&fixture.body), now);
+        assert_eq!(response.body, "3eZbrw1aBm2rZgRNFdxV2595E9CY3gmdALWMmHkvFXO7tYXAYM8P");
+        assert!(slack.sent().is_empty());
+    }
+
+    #[test]
+    fn ignores_forged_and_own_messages() {
+        let (bot, slack) = bot(SessionMode::Channel);
+        let body = message("U1", "C1", "crane");
+        let response = bot.handle_event(&HashMap::new(), Some(&body), now());
+        assert_eq!(response.status, 401);
+        assert_eq!(send(&bot, "UBOT", "C1", "crane"), 200);
+        assert!(slack.sent().is_empty());
+    }
+
+    #[test]
+    fn ignores_events_sent_again() {
+        let (bot, slack) = bot(SessionMode::Channel);
+        let body = message("U1", "C1", "aahed");
+        let mut headers = signed(&body, now());
+        headers.insert(RETRY_HEADER.to_string(), "1".to_string());
+        assert_eq!(bot.handle_event(&headers, Some(&body), now()).status, 200);
+        assert!(slack.sent().is_empty());
+    }
+
+    #[test]
+    fn answers_before_posting() {
+        let (bot, slack) = bot(SessionMode::Channel);
+        let bot = Arc::new(bot.posting_later());
+        assert_eq!(send(&bot, "U1", "C1", "aahed"), 200);
+        assert_eq!(send(&bot, "U1", "C1", "hint"), 200);
+        assert!(slack.sent().is_empty());
+
+        let poster = Arc::clone(&bot);
+        thread::spawn(move || poster.post_replies());
+        let deadline = Instant::now() + std::time::Duration::from_secs(5);
+        while slack.sent().len() < 2 && Instant::now() < deadline {
+            thread::sleep(std::time::Duration::from_millis(10));
+        }
+        let sent = slack.sent();
+        assert!(sent.len() >= 2, "{:?}", sent);
+        assert_eq!(sent[0].thread_ts, None);
+        assert_eq!(sent[1].thread_ts.as_deref(), Some("1.000000"));
+    }
+
+    #[test]
+    fn channels_have_their_own_games() {
+        let (bot, slack) = bot(SessionMode::Channel);
+        send(&bot, "U1", "C1", "aahed");
+        send(&bot, "U2", "C1", "hard mode on");
+        send(&bot, "U1", "C2", "hard mode on");
+        let sent = slack.take();
+        assert_eq!(sent[1].channel, "C1");
+        assert_eq!(sent[1].text, "Hard mode can only be turned on before the first guess.");
+        assert_eq!(sent[2].channel, "C2");
+        assert_eq!(sent[2].text, "Hard mode is on.");
+    }
+
+    #[test]
+    fn user_mode_mentions_the_player() {
+        let (bot, slack) = bot(SessionMode::User);
+        send(&bot, "U1", "C1", "aahed");
+        send(&bot, "U2", "C1", "hard mode on");
+        let sent = slack.take();
+        assert!(sent[0].text.starts_with("<@U1> "));
+        assert_eq!(sent[1].text, "<@U2> Hard mode is on.");
+    }
+
+    fn reply(bot: &Bot, user: &str, channel: &str, thread_ts: &str, text: &str) -> u16 {
+        let mut body: serde_json::Value = serde_json::from_str(&message(user, channel, text)).unwrap();
+        body["event"]["thread_ts"] = json!(thread_ts);
+        let body = body.to_string();
+        bot.handle_event(&signed(&body, now()), Some(&body), now()).status
+    }
+
+    #[test]
+    fn plays_in_a_thread() {
+        let (bot, slack) = bot(SessionMode::Channel);
+        send(&bot, "U1", "C1", "aahed");
+        reply(&bot, "U2", "C1", "1.000000", "hint");
+        reply(&bot, "U1", "C1", "9.000000", "hint");
+        send(&bot, "U1", "C1", "zzzzz");
+
+        let sent = slack.take();
+        assert_eq!(sent.len(), 4);
+        assert_eq!(sent[0].thread_ts, None);
+        assert!(sent[0].text.contains("   A   A   H   E   D"));
+        assert_eq!(sent[1].thread_ts, Some("1.000000".to_string()));
+        assert!(sent[1].text.ends_with("possible answers left."));
+        assert!(!sent[1].broadcast);
+        assert_eq!(sent[2].updated, Some("1.000000".to_string()));
+        assert!(sent[2].text.contains("   A   A   H   E   D"));
+        assert_eq!(
+            sent[2].blocks.as_ref().unwrap()[1]["elements"][0]["text"],
+            "Guess 1/6 · 1 hint"
+        );
+        // other threads are ignored, and private replies go to the game's thread too
+        assert_eq!(sent[3].user, Some("U1".to_string()));
+        assert_eq!(sent[3].thread_ts, Some("1.000000".to_string()));
+    }
+
+    #[test]
+    fn broadcasts_the_result() {
+        let (bot, slack) = bot(SessionMode::User);
+        send(&bot, "U1", "C1", "aahed");
+        reply(&bot, "U1", "C1", "1.000000", "giveup");
+        send(&bot, "U1", "C1", "aahed");
+
+        let sent = slack.take();
+        assert!(sent[1].text.starts_with("<@U1> You gave up!"));
+        assert!(sent[1].broadcast);
+        assert_eq!(sent[2].updated, Some("1.000000".to_string()));
+        assert_eq!(sent[2].blocks.as_ref().unwrap()[0]["text"]["text"], "<@U1>'s game");
+        // the next game gets a thread of its own
+        assert_eq!(sent[3].thread_ts, None);
+        assert_eq!(sent.len(), 4);
+    }
+
+    #[test]
+    fn reports_bad_guesses_privately() {
+        let (bot, slack) = bot(SessionMode::Channel);
+        send(&bot, "U1", "C1", "zzzzz");
+        let sent = slack.take();
+        assert_eq!(sent[0].text, "zzzzz is not in the dictionary!");
+        assert_eq!(sent[0].user, Some("U1".to_string()));
+    }
+
+    fn command(bot: &Bot, user: &str, text: &str) -> u16 {
+        let body = serde_urlencoded::to_string([
+            ("token", "gIkuvaNzQIHg97ATvDxqgjtO"),
+            ("team_id", "T0001"),
+            ("channel_id", "C2147483705"),
+            ("user_id", user),
+            ("command", "/wordle"),
+            ("text", text),
+        ])
+        .unwrap();
+        bot.handle_command(&signed(&body, now()), Some(&body), now()).status
+    }
+
+    #[test]
+    fn slash_commands() {
+        let (bot, slack) = bot(SessionMode::Channel);
+        assert_eq!(command(&bot, "U1", "new 6"), 200);
+        assert_eq!(command(&bot, "U1", "new"), 200);
+        assert_eq!(command(&bot, "U1", "guess zzzzz"), 200);
+        // a word that's allowed but never the answer, so the game can't end here
+        assert_eq!(command(&bot, "U2", "guess aahed"), 200);
+        assert_eq!(command(&bot, "U2", "board"), 200);
+        assert_eq!(command(&bot, "U1", "giveup"), 200);
+        assert_eq!(command(&bot, "U1", "stats"), 200);
+        assert_eq!(command(&bot, "U1", "help"), 200);
+        assert_eq!(command(&bot, "U1", "cheat please"), 200);
+
+        let mut sent = slack.take();
+        assert_eq!(sent[0].text, "New game! Guess the 6 letter word in 6 tries.");
+        assert_eq!(sent[0].user, None);
+        sent.remove(0);
+        assert_eq!(sent[0].text, "New game! Guess the 5 letter word in 6 tries.");
+        assert_eq!(sent[1].text, "zzzzz is not in the dictionary!");
+        assert_eq!(sent[1].user, Some("U1".to_string()));
+        assert!(sent[2].text.contains("   A   A   H   E   D"), "{}", sent[2].text);
+        assert_eq!(sent[2].user, None);
+        assert_eq!(sent[3].updated, Some("2.000000".to_string()));
+        assert_eq!(sent[4].user, Some("U2".to_string()));
+        assert!(sent[5].text.starts_with("You gave up! The word was "));
+        assert!(sent[5].text.contains("Wordle X/6"));
+        assert!(sent[5].broadcast);
+        assert_eq!(sent[6].updated, Some("2.000000".to_string()));
+        assert_eq!(
+            sent[7].text,
+            "You haven't finished any games of your own yet.\n\nThis channel has played 1, won 0 (0%)."
+        );
+        assert!(sent[8].text.starts_with("*Wordle commands*"));
+        assert!(sent[9].text.starts_with("I don't know how to"));
+        assert!(sent[7..].iter().all(|m| m.user.is_some()));
+    }
+
+    #[test]
+    fn slash_command_for_missing_length() {
+        let (bot, slack) = bot(SessionMode::User);
+        command(&bot, "U1", "new 12");
+        let sent = slack.take();
+        assert_eq!(sent[0].text, "There are no 12 letter words to play with.");
+        assert_eq!(sent[0].user, Some("U1".to_string()));
+    }
+
+    fn press(bot: &Bot, user: &str, action_id: &str) -> u16 {
+        let payload = json!({
+            "type": "block_actions",
+            "team": { "id": "T0001", "domain": "example" },
+            "user": { "id": user, "username": "roadrunner" },
+            "channel": { "id": "C2147483705", "name": "wordle" },
+            "actions": [{ "type": "button", "action_id": action_id, "block_id": "x7Wq", "action_ts": "1548426417.840180" }],
+        });
+        let body = serde_urlencoded::to_string([("payload", payload.to_string())]).unwrap();
+        bot.handle_interaction(&signed(&body, now()), Some(&body), now()).status
+    }
+
+    #[test]
+    fn replies_with_blocks() {
+        let (bot, slack) = bot(SessionMode::User);
+        command(&bot, "U1", "aahed");
+        let sent = slack.take();
+        let blocks = sent[0].blocks.as_ref().unwrap();
+        assert_eq!(blocks[0]["text"]["text"], "<@U1>'s game");
+        assert!(blocks[1]["text"]["text"]
+            .as_str()
+            .unwrap()
+            .ends_with("`AAHED`\n⬛⬛⬛⬛⬛\n⬛⬛⬛⬛⬛\n⬛⬛⬛⬛⬛\n⬛⬛⬛⬛⬛\n⬛⬛⬛⬛⬛"));
+    }
+
+    #[test]
+    fn buttons() {
+        let (bot, slack) = bot(SessionMode::Channel);
+        command(&bot, "U1", "aahed");
+        assert_eq!(press(&bot, "U2", blocks::HINT_ACTION), 200);
+        assert_eq!(press(&bot, "U2", blocks::NEW_GAME_ACTION), 200);
+        assert_eq!(press(&bot, "U2", "something_else"), 200);
+
+        let sent = slack.take();
+        assert_eq!(sent.len(), 4);
+        assert!(sent[1].text.ends_with("possible answers left."), "{}", sent[1].text);
+        assert_eq!(sent[1].channel, "C2147483705");
+        assert_eq!(sent[2].updated, Some("1.000000".to_string()));
+        assert_eq!(sent[3].text, "New game! Guess the 5 letter word in 6 tries.");
+        assert_eq!(sent[3].thread_ts, None);
+        assert!(sent[3].blocks.is_some());
+    }
+
+    #[test]
+    fn rejects_bad_interactions() {
+        let (bot, slack) = bot(SessionMode::Channel);
+        let body = "payload=%7B%22type%22%3A%22block_actions%22%7D";
+        assert_eq!(bot.handle_interaction(&HashMap::new(), Some(body), now()).status, 401);
+        assert_eq!(
+            bot.handle_interaction(&signed("payload=%7B", now()), Some("payload=%7B"), now())
+                .status,
+            400
+        );
+        assert_eq!(
+            bot.handle_interaction(&signed("text=hi", now()), Some("text=hi"), now())
+                .status,
+            400
+        );
+        assert_eq!(
+            bot.handle_interaction(&signed(body, now()), Some(body), now()).status,
+            400
+        );
+        assert!(slack.sent().is_empty());
+    }
+
+    #[test]
+    fn rejects_bad_slash_commands() {
+        let (bot, slack) = bot(SessionMode::Channel);
+        let body = "team_id=T0001&text=hint";
+        assert_eq!(bot.handle_command(&HashMap::new(), Some(body), now()).status, 401);
+        assert_eq!(bot.handle_command(&signed(body, now()), Some(body), now()).status, 400);
+        assert!(slack.sent().is_empty());
+    }
+
+    fn direct_message(bot: &Bot, user: &str, text: &str) -> u16 {
+        let mut body: serde_json::Value = serde_json::from_str(&message(user, "D1", text)).unwrap();
+        body["event"]["channel_type"] = json!("im");
+        let body = body.to_string();
+        bot.handle_event(&signed(&body, now()), Some(&body), now()).status
+    }
+
+    #[test]
+    fn daily_puzzle_is_private() {
+        let (bot, slack) = bot(SessionMode::Channel);
+        command(&bot, "U1", "aahed");
+        command(&bot, "U1", "daily aahed");
+        direct_message(&bot, "U2", "aahed");
+        direct_message(&bot, "U2", "board");
+        direct_message(&bot, "U2", "hint");
+
+        let sent = slack.take();
+        assert_eq!(sent[1].user, Some("U1".to_string()));
+        assert_eq!(sent[1].thread_ts, None);
+        assert!(sent[1].text.contains("   A   A   H   E   D"));
+        let last_block = sent[1]
+            .blocks
+            .as_ref()
+            .unwrap()
+            .as_array()
+            .unwrap()
+            .last()
+            .unwrap()
+            .clone();
+        assert_ne!(last_block["type"], "actions");
+        assert_eq!(sent[2].channel, "D1");
+        assert_eq!(sent[2].user, None);
+        assert!(sent[2].text.contains("   A   A   H   E   D"));
+        assert_eq!(sent[3].text, sent[2].text);
+        assert_eq!(
+            sent[4].text,
+            "Here you can only play the daily puzzle. Send me a guess!"
+        );
+    }
+
+    #[test]
+    fn themes_are_per_user() {
+        let (bot, slack) = bot(SessionMode::User);
+        command(&bot, "U1", "theme dark");
+        command(&bot, "U1", "theme");
+        command(&bot, "U1", "theme neon");
+        command(&bot, "U1", "aahed");
+        command(&bot, "U2", "aahed");
+        direct_message(&bot, "U2", "theme light");
+
+        let sent = slack.take();
+        assert_eq!(sent[0].text, "Your boards will be in the dark theme from now on.");
+        assert!(sent[1].text.starts_with("Your boards are in the dark theme."));
+        assert_eq!(
+            sent[2].text,
+            "\"neon\" isn't a theme. Try standard, high-contrast, dark or light."
+        );
+        assert!(sent[3].text.contains("🔲"), "{}", sent[3].text);
+        assert!(!sent[4].text.contains("🔲"), "{}", sent[4].text);
+        assert_eq!(sent[5].text, "Your boards will be in the light theme from now on.");
+    }
+
+    #[test]
+    fn announces_the_daily_puzzle_and_its_results() {
+        let (bot, slack) = bot(SessionMode::Channel);
+        let today = now().with_timezone(&Local).date_naive();
+        let puzzle = daily::puzzle_number(today).unwrap();
+        let answer = daily::answer(WebDictionary::new().answers(5), puzzle)
+            .unwrap()
+            .to_lowercase();
+        bot.run_task(Task::Digest(today));
+        bot.run_task(Task::Announce(today));
+        command(&bot, "U1", "daily aahed");
+        command(&bot, "U2", &format!("daily {}", answer));
+        for _ in 0..6 {
+            command(&bot, "U3", "daily aahed");
+        }
+        bot.run_task(Task::Digest(today));
+
+        let sent = slack.take();
+        assert_eq!(sent[0].text, format!("Nobody played Wordle {}.", puzzle));
+        assert_eq!(sent[0].channel, "rust-wordle-bot");
+        assert!(sent[1].text.starts_with(&format!("Wordle {} is ready!", puzzle)));
+        let digest = &sent.last().unwrap().text;
+        let expected = format!(
+            "*Wordle {0} results*\n\n<@U2> · streak 1\nWordle {0} 1/6\n\n🟩🟩🟩🟩🟩\n\n<@U3> · streak 0\nWordle {0} X/6",
+            puzzle
+        );
+        assert!(digest.starts_with(&expected), "{}", digest);
+        assert!(digest.ends_with("\n\n<@U1> is still playing, 1/6 so far"), "{}", digest);
+        assert!(!digest.contains("AAHED") && !digest.contains(&answer.to_uppercase()));
+    }
+
+    #[test]
+    fn keeps_stats_per_player() {
+        let (bot, slack) = bot(SessionMode::User);
+        command(&bot, "U1", "aahed");
+        command(&bot, "U1", "giveup");
+        command(&bot, "U1", "stats");
+        command(&bot, "U2", "stats");
+
+        let sent = slack.take();
+        let mine = &sent[sent.len() - 2].text;
+        assert!(
+            mine.starts_with("*Your stats*\nPlayed 1 · Win % 0 · Current streak 0"),
+            "{}",
+            mine
+        );
+        assert!(!mine.contains("This channel"));
+        assert_eq!(
+            sent.last().unwrap().text,
+            "You haven't finished any games of your own yet."
+        );
+    }
+
+    #[test]
+    fn replies_through_the_web_api() {
+        use crate::slack::tests::{http_response, mock_server};
+
+        let posted = r#"{"ok":true,"channel":"C1","ts":"1503435956.000247"}"#;
+        let (url, server) = mock_server(vec![http_response("200 OK", &[], posted)]);
+        let client = ReqwestClient::with_base_url("xoxb-1234", &url).unwrap();
+        let bot = bot_with(SessionMode::Channel, Box::new(client));
+        send(&bot, "U1", "C1", "hint");
+
+        let recorded = server.join().unwrap();
+        assert_eq!(recorded[0].path, "/api/chat.postMessage");
+        assert_eq!(recorded[0].body["channel"], "C1");
+        assert!(recorded[0].body["text"]
+            .as_str()
+            .unwrap()
+            .ends_with("possible answers left."));
+    }
+}
@@ -0,0 +1,252 @@
+use std::path::PathBuf;
+
+use chrono::NaiveDate;
+use clap::builder::RangedU64ValueParser;
+use clap::{Args, Parser, Subcommand};
+
+use crate::board::BoardConfig;
+use crate::theme::Theme;
+
+/// Play Wordle in the terminal, watch the solver play, or run the Slack bot.
+#[derive(Debug, Parser)]
+#[command(name = "wordle", version, about)]
+pub struct Cli {
+    /// Accept guesses from this word list instead of the configured one
+    #[arg(long, global = true, value_name = "PATH")]
+    pub dictionary: Option<PathBuf>,
+
+    /// Change the board at once instead of turning tiles over, for slow terminals and CI
+    #[arg(long, global = true)]
+    pub no_animations: bool,
+
+    /// Colours to play in: standard, high-contrast, dark or light. Remembered for next time
+    #[arg(long, global = true, value_name = "THEME")]
+    pub theme: Option<Theme>,
+
+    #[command(subcommand)]
+    pub command: Option<CliCommand>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum CliCommand {
+    /// Play in the terminal, picking up the last game unless any game options are given (the default)
+    Play(GameArgs),
+    /// Run the Slack bot, configured by wordle.toml and the environment
+    ServeSlack,
+    /// Watch the solver play a game
+    Solve(GameArgs),
+    /// Play the daily puzzle in the terminal
+    Daily(DailyArgs),
+    /// Show the solver's best next guesses for the game you're playing
+    Suggest {
+        /// How many guesses to show
+        #[arg(short = 'n', long, default_value_t = 5, value_parser = RangedU64ValueParser::<usize>::new().range(1..))]
+        count: usize,
+
+        /// For the daily puzzle rather than your own game
+        #[arg(long)]
+        daily: bool,
+    },
+    /// Show how your terminal games have gone
+    Stats,
+    /// Check words against the word lists, or count the words of each length
+    Dict {
+        /// Words to look up
+        words: Vec<String>,
+    },
+}
+
+/// The shape of the board, shared by every kind of game.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Args)]
+pub struct BoardArgs {
+    /// Letters in the secret word [default: 5]
+    #[arg(short, long, value_parser = RangedU64ValueParser::<usize>::new().range(3..=12))]
+    pub length: Option<usize>,
+
+    /// Guesses allowed before the game is lost [default: 6]
+    #[arg(short = 'g', long, value_parser = RangedU64ValueParser::<usize>::new().range(1..=20))]
+    pub max_guesses: Option<usize>,
+
+    /// Every guess has to use what earlier ones revealed
+    #[arg(long)]
+    pub hard: bool,
+}
+
+impl BoardArgs {
+    pub fn config(&self) -> BoardConfig {
+        let default = BoardConfig::default();
+        BoardConfig {
+            word_length: self.length.unwrap_or(default.word_length),
+            max_guesses: self.max_guesses.unwrap_or(default.max_guesses),
+            hard_mode: self.hard,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Args)]
+pub struct GameArgs {
+    #[command(flatten)]
+    pub board: BoardArgs,
+
+    /// Pick the secret word with this seed, so the same seed always gives the same game
+    #[arg(long, conflicts_with = "secret")]
+    pub seed: Option<u64>,
+
+    /// Play with this secret word instead of a random one
+    #[arg(long, value_parser = parse_word)]
+    pub secret: Option<String>,
+
+    /// Start a new game even if there's one to pick up
+    #[arg(long)]
+    pub new: bool,
+}
+
+impl GameArgs {
+    /// The board for this game. The word length follows the secret word unless it's given too, in
+    /// which case they have to agree.
+    pub fn config(&self) -> Result<BoardConfig, String> {
+        let mut config = self.board.config();
+        if let Some(secret) = &self.secret {
+            let length = secret.chars().count();
+            match self.board.length {
+                Some(expected) if expected != length => {
+                    return Err(format!(
+                        "the secret word {:?} has {} letters, not {}",
+                        secret, length, expected
+                    ))
+                }
+                _ => config.word_length = length,
+            }
+        }
+        Ok(config)
+    }
+
+    /// Whether anything was asked for that a saved game might not match.
+    pub fn is_new_game(&self) -> bool {
+        self.new || *self != GameArgs::default()
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Args)]
+pub struct DailyArgs {
+    #[command(flatten)]
+    pub board: BoardArgs,
+
+    /// Play the puzzle for another day, such as 2022-08-05
+    #[arg(long)]
+    pub date: Option<NaiveDate>,
+}
+
+/// A word on the command line, in any case.
+fn parse_word(word: &str) -> Result<String, String> {
+    if !word.is_empty() && word.chars().all(|c| c.is_ascii_alphabetic()) {
+        Ok(word.to_lowercase())
+    } else {
+        Err("expected a word made of letters".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::error::ErrorKind;
+    use clap::{CommandFactory, Parser};
+
+    use crate::board::BoardConfig;
+    use crate::cli::{Cli, CliCommand, GameArgs};
+    use crate::theme::Theme;
+
+    fn parse(args: &[&str]) -> Result<Cli, clap::Error> {
+        Cli::try_parse_from(std::iter::once("wordle").chain(args.iter().copied()))
+    }
+
+    fn game(args: &[&str]) -> GameArgs {
+        match parse(args).unwrap().command {
+            Some(CliCommand::Play(args)) | Some(CliCommand::Solve(args)) => args,
+            command => panic!("expected a game, got {:?}", command),
+        }
+    }
+
+    #[test]
+    fn definition_is_valid() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn parses_subcommands() {
+        assert!(parse(&[]).unwrap().command.is_none());
+        assert!(matches!(
+            parse(&["serve-slack"]).unwrap().command,
+            Some(CliCommand::ServeSlack)
+        ));
+        assert!(matches!(parse(&["stats"]).unwrap().command, Some(CliCommand::Stats)));
+        assert!(matches!(
+            parse(&["suggest"]).unwrap().command,
+            Some(CliCommand::Suggest { count: 5, daily: false })
+        ));
+        assert!(matches!(
+            parse(&["suggest", "-n", "10", "--daily"]).unwrap().command,
+            Some(CliCommand::Suggest { count: 10, daily: true })
+        ));
+        match parse(&["dict", "crane", "tulip"]).unwrap().command {
+            Some(CliCommand::Dict { words }) => assert_eq!(words, vec!["crane", "tulip"]),
+            command => panic!("{:?}", command),
+        }
+        match parse(&["daily", "--date", "2022-08-05", "--hard"]).unwrap().command {
+            Some(CliCommand::Daily(args)) => {
+                assert_eq!(args.date.unwrap().to_string(), "2022-08-05");
+                assert!(args.board.hard);
+            }
+            command => panic!("{:?}", command),
+        }
+        let cli = parse(&["solve", "--dictionary", "words.txt"]).unwrap();
+        assert_eq!(cli.dictionary.unwrap().to_str(), Some("words.txt"));
+        assert!(!cli.no_animations);
+        assert!(parse(&["daily", "--no-animations"]).unwrap().no_animations);
+        assert_eq!(
+            parse(&["--theme", "high-contrast"]).unwrap().theme,
+            Some(Theme::HighContrast)
+        );
+    }
+
+    #[test]
+    fn game_options() {
+        let args = game(&["play"]);
+        assert!(!args.is_new_game());
+        assert_eq!(args.config(), Ok(BoardConfig::default()));
+
+        let args = game(&["play", "-l", "6", "-g", "8", "--hard", "--seed", "42"]);
+        assert!(args.is_new_game());
+        assert_eq!(args.seed, Some(42));
+        let config = args.config().unwrap();
+        assert_eq!((config.word_length, config.max_guesses, config.hard_mode), (6, 8, true));
+
+        let args = game(&["solve", "--secret", "Tulip"]);
+        assert_eq!(args.secret.as_deref(), Some("tulip"));
+        assert_eq!(args.config().unwrap().word_length, 5);
+        assert_eq!(
+            game(&["play", "--secret", "rusty", "--length", "5"])
+                .config()
+                .unwrap()
+                .word_length,
+            5
+        );
+        assert!(game(&["play", "--secret", "rusty", "--length", "6"]).config().is_err());
+    }
+
+    #[test]
+    fn rejects_bad_arguments() {
+        let kind = |args: &[&str]| parse(args).unwrap_err().kind();
+        assert_eq!(kind(&["play", "--length", "2"]), ErrorKind::ValueValidation);
+        assert_eq!(kind(&["play", "--max-guesses", "0"]), ErrorKind::ValueValidation);
+        assert_eq!(kind(&["play", "--secret", "c4ne"]), ErrorKind::ValueValidation);
+        assert_eq!(
+            kind(&["play", "--secret", "crane", "--seed", "1"]),
+            ErrorKind::ArgumentConflict
+        );
+        assert_eq!(kind(&["daily", "--date", "tomorrow"]), ErrorKind::ValueValidation);
+        assert_eq!(kind(&["cheat"]), ErrorKind::InvalidSubcommand);
+        assert_eq!(kind(&["suggest", "-n", "0"]), ErrorKind::ValueValidation);
+        assert_eq!(kind(&["play", "--theme", "neon"]), ErrorKind::ValueValidation);
+        assert_eq!(kind(&["--help"]), ErrorKind::DisplayHelp);
+    }
+}
@@ -0,0 +1,179 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::theme::Theme;
+
+/// What a player can ask the bot to do, either with `/wordle ...` or in a message to it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    /// Start over, optionally with a different word length.
+    New(Option<usize>),
+    Guess(String),
+    Board,
+    Hint,
+    GiveUp,
+    /// Today's puzzle, played privately. Without a guess it shows the board.
+    Daily(Option<String>),
+    Stats,
+    /// Show the player's colours, or change them.
+    Theme(Option<Theme>),
+    Help,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CommandError {
+    Unknown(String),
+    MissingWord,
+    BadLength(String),
+    BadTheme(String),
+    UnexpectedArgument { command: &'static str, argument: String },
+}
+
+impl fmt::Display for CommandError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CommandError::Unknown(command) => {
+                write!(f, "I don't know how to {:?}. Try `/wordle help`.", command)
+            }
+            CommandError::MissingWord => write!(f, "What's your guess? Try `/wordle guess crane`."),
+            CommandError::BadLength(length) => write!(f, "{:?} isn't a word length.", length),
+            CommandError::BadTheme(theme) => {
+                write!(f, "{:?} isn't a theme. Try {}.", theme, Theme::names())
+            }
+            CommandError::UnexpectedArgument { command, argument } => {
+                write!(f, "`{}` doesn't take {:?}.", command, argument)
+            }
+        }
+    }
+}
+
+impl std::error::Error for CommandError {}
+
+pub const HELP: &str = "*Wordle commands*
+`/wordle new [length]` start a new game, optionally with 3 to 12 letters
+`/wordle guess <word>` or just `/wordle <word>` make a guess
+`/wordle board` show the game so far
+`/wordle hint` get a hint, each one more revealing than the last
+`/wordle giveup` end the game and reveal the word
+`/wordle daily [word]` play today's puzzle where only you can see it, or DM me your guesses
+`/wordle stats` see your record, streaks and guess distribution
+`/wordle theme [name]` see or change your colours: standard, high-contrast, dark or light
+`/wordle help` show this message";
+
+impl FromStr for Command {
+    type Err = CommandError;
+
+    /// Commands are case-insensitive, and a lone word that isn't a command is taken as a guess.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut words = s.split_whitespace();
+        let name = match words.next() {
+            Some(name) => name.to_lowercase(),
+            None => return Ok(Command::Help),
+        };
+        let argument = words.next();
+        if let Some(extra) = words.next() {
+            return Err(CommandError::UnexpectedArgument {
+                command: "wordle",
+                argument: extra.to_string(),
+            });
+        }
+
+        let no_argument = |command: &'static str, parsed: Command| match argument {
+            Some(argument) => Err(CommandError::UnexpectedArgument {
+                command,
+                argument: argument.to_string(),
+            }),
+            None => Ok(parsed),
+        };
+        match name.as_str() {
+            "new" | "start" => match argument {
+                Some(length) => match length.parse() {
+                    Ok(length) => Ok(Command::New(Some(length))),
+                    Err(_) => Err(CommandError::BadLength(length.to_string())),
+                },
+                None => Ok(Command::New(None)),
+            },
+            "guess" => match argument {
+                Some(word) => Ok(Command::Guess(word.to_lowercase())),
+                None => Err(CommandError::MissingWord),
+            },
+            "board" | "show" => no_argument("board", Command::Board),
+            "hint" => no_argument("hint", Command::Hint),
+            "giveup" | "give-up" | "quit" => no_argument("giveup", Command::GiveUp),
+            "daily" | "today" => Ok(Command::Daily(argument.map(str::to_lowercase))),
+            "stats" => no_argument("stats", Command::Stats),
+            "theme" | "colours" | "colors" => match argument {
+                Some(name) => match name.parse() {
+                    Ok(theme) => Ok(Command::Theme(Some(theme))),
+                    Err(_) => Err(CommandError::BadTheme(name.to_string())),
+                },
+                None => Ok(Command::Theme(None)),
+            },
+            "help" => no_argument("help", Command::Help),
+            word if argument.is_none() && word.chars().all(|c| c.is_alphabetic()) => {
+                Ok(Command::Guess(word.to_string()))
+            }
+            _ => Err(CommandError::Unknown(s.trim().to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::command::{Command, CommandError};
+    use crate::theme::Theme;
+
+    fn parse(s: &str) -> Result<Command, CommandError> {
+        s.parse()
+    }
+
+    #[test]
+    fn parses_commands() {
+        assert_eq!(parse("new"), Ok(Command::New(None)));
+        assert_eq!(parse("new 6"), Ok(Command::New(Some(6))));
+        assert_eq!(parse("guess crane"), Ok(Command::Guess("crane".to_string())));
+        assert_eq!(parse("board"), Ok(Command::Board));
+        assert_eq!(parse("hint"), Ok(Command::Hint));
+        assert_eq!(parse("giveup"), Ok(Command::GiveUp));
+        assert_eq!(parse("daily"), Ok(Command::Daily(None)));
+        assert_eq!(parse("daily Crane"), Ok(Command::Daily(Some("crane".to_string()))));
+        assert_eq!(parse("stats"), Ok(Command::Stats));
+        assert_eq!(parse("theme"), Ok(Command::Theme(None)));
+        assert_eq!(
+            parse("theme High-Contrast"),
+            Ok(Command::Theme(Some(Theme::HighContrast)))
+        );
+        assert_eq!(parse("help"), Ok(Command::Help));
+    }
+
+    #[test]
+    fn is_forgiving() {
+        assert_eq!(parse("  HINT "), Ok(Command::Hint));
+        assert_eq!(parse("give-up"), Ok(Command::GiveUp));
+        assert_eq!(parse(""), Ok(Command::Help));
+        assert_eq!(parse("Crane"), Ok(Command::Guess("crane".to_string())));
+    }
+
+    #[test]
+    fn rejects_bad_commands() {
+        assert_eq!(parse("guess"), Err(CommandError::MissingWord));
+        assert_eq!(parse("new five"), Err(CommandError::BadLength("five".to_string())));
+        assert_eq!(parse("colours neon"), Err(CommandError::BadTheme("neon".to_string())));
+        assert_eq!(
+            parse("cheat please"),
+            Err(CommandError::Unknown("cheat please".to_string()))
+        );
+        assert_eq!(parse("c4ne"), Err(CommandError::Unknown("c4ne".to_string())));
+        assert_eq!(
+            parse("hint now"),
+            Err(CommandError::UnexpectedArgument {
+                command: "hint",
+                argument: "now".to_string()
+            })
+        );
+        assert!(matches!(
+            parse("guess crane now"),
+            Err(CommandError::UnexpectedArgument { .. })
+        ));
+    }
+}
@@ -0,0 +1,474 @@
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::net::SocketAddr;
+use std::path::Path;
+
+use chrono::NaiveTime;
+use serde::Deserialize;
+
+use crate::dictionary::{ANSWERS_PATH, DICTIONARY_PATH};
+use crate::schedule::Schedule;
+use crate::session::SessionMode;
+
+/// Where settings are read from unless `WORDLE_CONFIG` points somewhere else. It's fine for it not to
+/// exist as long as the environment covers everything.
+pub const CONFIG_PATH: &str = "./wordle.toml";
+pub const DEFAULT_LISTEN_ADDRESS: &str = "0.0.0.0:3000";
+pub const DEFAULT_API_URL: &str = "https://slack.com/api";
+pub const DEFAULT_DIGEST_AT: &str = "17:00";
+
+/// A setting's name in the config file and the environment variable that overrides it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Setting {
+    pub key: &'static str,
+    pub var: &'static str,
+}
+
+pub const DICTIONARY: Setting = Setting {
+    key: "dictionary_path",
+    var: "WORDLE_DICTIONARY_PATH",
+};
+pub const ANSWERS: Setting = Setting {
+    key: "answers_path",
+    var: "WORDLE_ANSWERS_PATH",
+};
+pub const TOKEN: Setting = Setting {
+    key: "slack.token",
+    var: "SLACK_TOKEN",
+};
+pub const SIGNING_SECRET: Setting = Setting {
+    key: "slack.signing_secret",
+    var: "SLACK_SIGNING_SECRET",
+};
+pub const BOT_USER_ID: Setting = Setting {
+    key: "slack.bot_user_id",
+    var: "SLACK_BOT_USER_ID",
+};
+pub const DEFAULT_CHANNEL: Setting = Setting {
+    key: "slack.default_channel",
+    var: "SLACK_DEFAULT_CHANNEL",
+};
+pub const LISTEN_ADDRESS: Setting = Setting {
+    key: "slack.listen_address",
+    var: "WORDLE_LISTEN_ADDRESS",
+};
+pub const API_URL: Setting = Setting {
+    key: "slack.api_url",
+    var: "SLACK_API_URL",
+};
+pub const SESSION_MODE: Setting = Setting {
+    key: "slack.session_mode",
+    var: "WORDLE_SESSION_MODE",
+};
+pub const DAILY_ANNOUNCE_AT: Setting = Setting {
+    key: "slack.daily_announce_at",
+    var: "WORDLE_DAILY_ANNOUNCE_AT",
+};
+pub const DAILY_DIGEST_AT: Setting = Setting {
+    key: "slack.daily_digest_at",
+    var: "WORDLE_DAILY_DIGEST_AT",
+};
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Read { path: String, source: io::Error },
+    Parse { path: String, source: toml::de::Error },
+    Missing(Setting),
+    Invalid { setting: Setting, message: String },
+    NoSlack,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::Read { path, source } => write!(f, "couldn't read {}: {}", path, source),
+            ConfigError::Parse { path, source } => write!(f, "{} isn't valid: {}", path, source),
+            ConfigError::Missing(setting) => write!(
+                f,
+                "{} is not set, add it to the config file or set {}",
+                setting.key, setting.var
+            ),
+            ConfigError::Invalid { setting, message } => {
+                write!(f, "{} (or {}) is invalid: {}", setting.key, setting.var, message)
+            }
+            ConfigError::NoSlack => write!(
+                f,
+                "the Slack bot isn't configured, add a [slack] section to the config file or set {}",
+                TOKEN.var
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Config {
+    pub dictionary_path: String,
+    pub answers_path: String,
+    /// Only needed to run the bot, so the terminal game works without it.
+    pub slack: Option<SlackConfig>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SlackConfig {
+    pub token: String,
+    pub signing_secret: String,
+    pub bot_user_id: String,
+    /// Where the bot posts when it isn't replying to anyone, without the leading #.
+    pub default_channel: String,
+    pub listen_address: SocketAddr,
+    /// Where the Web API is, which only changes to point the bot at a mock or a proxy.
+    pub api_url: String,
+    pub session_mode: SessionMode,
+    /// When to post the daily puzzle and its results, if at all.
+    pub daily: Option<Schedule>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConfigFile {
+    dictionary_path: Option<String>,
+    answers_path: Option<String>,
+    slack: Option<SlackFile>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SlackFile {
+    token: Option<String>,
+    signing_secret: Option<String>,
+    bot_user_id: Option<String>,
+    default_channel: Option<String>,
+    listen_address: Option<String>,
+    api_url: Option<String>,
+    session_mode: Option<String>,
+    daily_announce_at: Option<String>,
+    daily_digest_at: Option<String>,
+}
+
+impl Config {
+    /// Reads `WORDLE_CONFIG` (or `./wordle.toml`) and applies any overrides from the environment.
+    pub fn load() -> Result<Self, ConfigError> {
+        let path = env::var("WORDLE_CONFIG").unwrap_or_else(|_| CONFIG_PATH.to_string());
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound && env::var_os("WORDLE_CONFIG").is_none() => String::new(),
+            Err(source) => return Err(ConfigError::Read { path, source }),
+        };
+        let config = Config::parse(&text, |var| env::var(var).ok()).map_err(|e| match e {
+            ConfigError::Parse { source, .. } => ConfigError::Parse { path, source },
+            e => e,
+        })?;
+        config.check_files()?;
+        Ok(config)
+    }
+
+    /// Builds a config from the contents of a config file, with `env` looking up overrides.
+    pub fn parse<E: Fn(&str) -> Option<String>>(text: &str, env: E) -> Result<Self, ConfigError> {
+        let file: ConfigFile = toml::from_str(text).map_err(|source| ConfigError::Parse {
+            path: CONFIG_PATH.to_string(),
+            source,
+        })?;
+        // an empty variable counts as unset, which is what `FOO= wordle` usually means
+        let get = |setting: Setting, value: Option<String>| {
+            env(setting.var)
+                .filter(|v| !v.trim().is_empty())
+                .or(value)
+                .map(|v| v.trim().to_string())
+        };
+
+        let dictionary_path = get(DICTIONARY, file.dictionary_path).unwrap_or_else(|| DICTIONARY_PATH.to_string());
+        let answers_path = get(ANSWERS, file.answers_path).unwrap_or_else(|| ANSWERS_PATH.to_string());
+
+        let slack_file = file.slack.unwrap_or_default();
+        let token = get(TOKEN, slack_file.token);
+        let signing_secret = get(SIGNING_SECRET, slack_file.signing_secret);
+        let bot_user_id = get(BOT_USER_ID, slack_file.bot_user_id);
+        let default_channel = get(DEFAULT_CHANNEL, slack_file.default_channel);
+        let listen_address = get(LISTEN_ADDRESS, slack_file.listen_address);
+        let api_url = get(API_URL, slack_file.api_url);
+        let session_mode = get(SESSION_MODE, slack_file.session_mode);
+        let daily_announce_at = get(DAILY_ANNOUNCE_AT, slack_file.daily_announce_at);
+        let daily_digest_at = get(DAILY_DIGEST_AT, slack_file.daily_digest_at);
+
+        let configured = [
+            &token,
+            &signing_secret,
+            &bot_user_id,
+            &default_channel,
+            &listen_address,
+            &api_url,
+            &session_mode,
+            &daily_announce_at,
+            &daily_digest_at,
+        ]
+        .iter()
+        .any(|value| value.is_some());
+        let slack = if configured {
+            Some(SlackConfig {
+                token: validate_token(token.ok_or(ConfigError::Missing(TOKEN))?)?,
+                signing_secret: signing_secret.ok_or(ConfigError::Missing(SIGNING_SECRET))?,
+                bot_user_id: validate_user_id(bot_user_id.ok_or(ConfigError::Missing(BOT_USER_ID))?)?,
+                default_channel: validate_channel(default_channel.ok_or(ConfigError::Missing(DEFAULT_CHANNEL))?)?,
+                listen_address: listen_address
+                    .as_deref()
+                    .unwrap_or(DEFAULT_LISTEN_ADDRESS)
+                    .parse()
+                    .map_err(|e| invalid(LISTEN_ADDRESS, e))?,
+                api_url: validate_api_url(api_url.unwrap_or_else(|| DEFAULT_API_URL.to_string()))?,
+                session_mode: match session_mode {
+                    Some(mode) => mode.parse().map_err(|e| invalid(SESSION_MODE, e))?,
+                    None => SessionMode::Channel,
+                },
+                daily: parse_schedule(daily_announce_at, daily_digest_at)?,
+            })
+        } else {
+            None
+        };
+
+        Ok(Config {
+            dictionary_path,
+            answers_path,
+            slack,
+        })
+    }
+
+    /// The bot's settings, for commands that can't run without them.
+    pub fn slack(&self) -> Result<&SlackConfig, ConfigError> {
+        self.slack.as_ref().ok_or(ConfigError::NoSlack)
+    }
+
+    /// Catches a typo in a word list path before the first game tries to load it.
+    fn check_files(&self) -> Result<(), ConfigError> {
+        for (setting, path) in [(DICTIONARY, &self.dictionary_path), (ANSWERS, &self.answers_path)] {
+            if !Path::new(path).is_file() {
+                return Err(invalid(setting, format!("{} is not a file", path)));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn invalid<E: fmt::Display>(setting: Setting, message: E) -> ConfigError {
+    ConfigError::Invalid {
+        setting,
+        message: message.to_string(),
+    }
+}
+
+/// The daily puzzle is only announced if there's a time to announce it at. The digest time on its
+/// own means nothing, so it's an error rather than silently ignored.
+fn parse_schedule(announce_at: Option<String>, digest_at: Option<String>) -> Result<Option<Schedule>, ConfigError> {
+    let announce_at = match announce_at {
+        Some(announce_at) => parse_time(DAILY_ANNOUNCE_AT, &announce_at)?,
+        None if digest_at.is_some() => return Err(ConfigError::Missing(DAILY_ANNOUNCE_AT)),
+        None => return Ok(None),
+    };
+    let digest_at = parse_time(DAILY_DIGEST_AT, digest_at.as_deref().unwrap_or(DEFAULT_DIGEST_AT))?;
+    if digest_at <= announce_at {
+        return Err(invalid(
+            DAILY_DIGEST_AT,
+            format!(
+                "the results can't be posted before the puzzle is announced at {}",
+                announce_at.format("%H:%M")
+            ),
+        ));
+    }
+    Ok(Some(Schedule { announce_at, digest_at }))
+}
+
+/// "08:30", in the server's local time.
+fn parse_time(setting: Setting, time: &str) -> Result<NaiveTime, ConfigError> {
+    NaiveTime::parse_from_str(time, "%H:%M")
+        .map_err(|_| invalid(setting, format!("{:?} isn't a time like 08:30", time)))
+}
+
+fn validate_token(token: String) -> Result<String, ConfigError> {
+    if token.starts_with("xox") {
+        Ok(token)
+    } else {
+        Err(invalid(TOKEN, "expected a Slack token starting with xoxb-"))
+    }
+}
+
+fn validate_api_url(url: String) -> Result<String, ConfigError> {
+    if url.starts_with("https://") || url.starts_with("http://") {
+        Ok(url.trim_end_matches('/').to_string())
+    } else {
+        Err(invalid(API_URL, format!("{:?} isn't an http:// or https:// URL", url)))
+    }
+}
+
+fn validate_user_id(id: String) -> Result<String, ConfigError> {
+    let well_formed = (id.starts_with('U') || id.starts_with('W'))
+        && id.len() > 1
+        && id.chars().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit());
+    if well_formed {
+        Ok(id)
+    } else {
+        Err(invalid(
+            BOT_USER_ID,
+            format!("{:?} doesn't look like a Slack user id such as U03M2JWUMDY", id),
+        ))
+    }
+}
+
+fn validate_channel(channel: String) -> Result<String, ConfigError> {
+    let channel = channel.trim_start_matches('#').to_string();
+    if channel.is_empty() || channel.contains(char::is_whitespace) {
+        Err(invalid(
+            DEFAULT_CHANNEL,
+            format!("{:?} isn't a channel name or id", channel),
+        ))
+    } else {
+        Ok(channel)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use chrono::NaiveTime;
+
+    use crate::config::{
+        Config, ConfigError, API_URL, BOT_USER_ID, DAILY_ANNOUNCE_AT, DAILY_DIGEST_AT, DEFAULT_API_URL, LISTEN_ADDRESS,
+        SIGNING_SECRET, TOKEN,
+    };
+    use crate::dictionary::{ANSWERS_PATH, DICTIONARY_PATH};
+    use crate::session::SessionMode;
+
+    const FILE: &str = r##"
+dictionary_path = "./data/dictionary.txt"
+
+[slack]
+token = "xoxb-1234"
+signing_secret = "8f742231b10e8888abcd99yyyzzz85a5"
+bot_user_id = "U03M2JWUMDY"
+default_channel = "#rust-wordle-bot"
+"##;
+
+    fn parse(text: &str, env: &[(&str, &str)]) -> Result<Config, ConfigError> {
+        let env: HashMap<String, String> = env.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        Config::parse(text, |var| env.get(var).cloned())
+    }
+
+    #[test]
+    fn reads_file() {
+        let config = parse(FILE, &[]).unwrap();
+        assert_eq!(config.dictionary_path, DICTIONARY_PATH);
+        assert_eq!(config.answers_path, ANSWERS_PATH);
+        let slack = config.slack().unwrap();
+        assert_eq!(slack.token, "xoxb-1234");
+        assert_eq!(slack.bot_user_id, "U03M2JWUMDY");
+        assert_eq!(slack.default_channel, "rust-wordle-bot");
+        assert_eq!(slack.listen_address.to_string(), "0.0.0.0:3000");
+        assert_eq!(slack.api_url, DEFAULT_API_URL);
+        assert_eq!(slack.session_mode, SessionMode::Channel);
+        assert_eq!(slack.daily, None);
+    }
+
+    #[test]
+    fn environment_overrides_file() {
+        let env = [
+            ("SLACK_TOKEN", "xoxb-5678"),
+            ("WORDLE_LISTEN_ADDRESS", "127.0.0.1:8080"),
+            ("SLACK_API_URL", "http://127.0.0.1:9000/api/"),
+            ("WORDLE_SESSION_MODE", "user"),
+            ("SLACK_BOT_USER_ID", ""),
+        ];
+        let config = parse(FILE, &env).unwrap();
+        let slack = config.slack().unwrap();
+        assert_eq!(slack.token, "xoxb-5678");
+        assert_eq!(slack.listen_address.port(), 8080);
+        assert_eq!(slack.api_url, "http://127.0.0.1:9000/api");
+        assert_eq!(slack.session_mode, SessionMode::User);
+        assert_eq!(slack.bot_user_id, "U03M2JWUMDY");
+    }
+
+    #[test]
+    fn environment_alone_is_enough() {
+        let env = [
+            ("SLACK_TOKEN", "xoxb-1234"),
+            ("SLACK_SIGNING_SECRET", "secret"),
+            ("SLACK_BOT_USER_ID", "U03M2JWUMDY"),
+            ("SLACK_DEFAULT_CHANNEL", "C0123456789"),
+            ("WORDLE_DICTIONARY_PATH", "/usr/share/dict/words"),
+        ];
+        let config = parse("", &env).unwrap();
+        assert_eq!(config.dictionary_path, "/usr/share/dict/words");
+        assert_eq!(config.slack().unwrap().default_channel, "C0123456789");
+    }
+
+    #[test]
+    fn slack_is_optional() {
+        let config = parse("", &[]).unwrap();
+        assert!(config.slack.is_none());
+        assert!(matches!(config.slack(), Err(ConfigError::NoSlack)));
+    }
+
+    #[test]
+    fn partial_slack_config_is_an_error() {
+        let err = parse("[slack]\ntoken = \"xoxb-1234\"\n", &[]).unwrap_err();
+        assert!(matches!(err, ConfigError::Missing(setting) if setting == SIGNING_SECRET));
+        assert_eq!(
+            err.to_string(),
+            "slack.signing_secret is not set, add it to the config file or set SLACK_SIGNING_SECRET"
+        );
+    }
+
+    #[test]
+    fn rejects_bad_values() {
+        let err = parse(FILE, &[("SLACK_TOKEN", "SLACK_TOKEN_HERE")]).unwrap_err();
+        assert!(matches!(err, ConfigError::Invalid { setting, .. } if setting == TOKEN));
+
+        let err = parse(FILE, &[("SLACK_BOT_USER_ID", "rust-wordle-bot")]).unwrap_err();
+        assert!(matches!(err, ConfigError::Invalid { setting, .. } if setting == BOT_USER_ID));
+
+        let err = parse(FILE, &[("WORDLE_LISTEN_ADDRESS", "localhost")]).unwrap_err();
+        assert!(matches!(err, ConfigError::Invalid { setting, .. } if setting == LISTEN_ADDRESS));
+        assert!(err
+            .to_string()
+            .starts_with("slack.listen_address (or WORDLE_LISTEN_ADDRESS) is invalid"));
+
+        let err = parse(FILE, &[("SLACK_API_URL", "slack.com/api")]).unwrap_err();
+        assert!(matches!(err, ConfigError::Invalid { setting, .. } if setting == API_URL));
+    }
+
+    #[test]
+    fn daily_schedule() {
+        let config = parse(FILE, &[("WORDLE_DAILY_ANNOUNCE_AT", "08:30")]).unwrap();
+        let daily = config.slack().unwrap().daily.unwrap();
+        assert_eq!(daily.announce_at, NaiveTime::from_hms_opt(8, 30, 0).unwrap());
+        assert_eq!(daily.digest_at, NaiveTime::from_hms_opt(17, 0, 0).unwrap());
+
+        let err = parse(FILE, &[("WORDLE_DAILY_DIGEST_AT", "18:00")]).unwrap_err();
+        assert!(matches!(err, ConfigError::Missing(setting) if setting == DAILY_ANNOUNCE_AT));
+        let err = parse(FILE, &[("WORDLE_DAILY_ANNOUNCE_AT", "8am")]).unwrap_err();
+        assert!(matches!(err, ConfigError::Invalid { setting, .. } if setting == DAILY_ANNOUNCE_AT));
+        let env = [
+            ("WORDLE_DAILY_ANNOUNCE_AT", "18:00"),
+            ("WORDLE_DAILY_DIGEST_AT", "09:00"),
+        ];
+        let err = parse(FILE, &env).unwrap_err();
+        assert!(matches!(err, ConfigError::Invalid { setting, .. } if setting == DAILY_DIGEST_AT));
+    }
+
+    #[test]
+    fn example_file_is_valid() {
+        let config = parse(include_str!("../wordle.example.toml"), &[]).unwrap();
+        assert_eq!(config.answers_path, ANSWERS_PATH);
+        config.check_files().unwrap();
+        assert!(config.slack.is_some());
+    }
+
+    #[test]
+    fn rejects_unknown_keys() {
+        let err = parse("[slack]\ntokn = \"xoxb-1234\"\n", &[]).unwrap_err();
+        assert!(matches!(err, ConfigError::Parse { .. }));
+        assert!(err.to_string().contains("tokn"));
+    }
+}
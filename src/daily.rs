@@ -0,0 +1,72 @@
+use chrono::NaiveDate;
+use rand::RngCore;
+use rand_chacha::rand_core::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+/// Shuffles the answer list so consecutive puzzles aren't alphabetical. Changing this changes every
+/// past and future puzzle, so don't.
+const SHUFFLE_SEED: u64 = 0x5255_5354_5946_5249; // "RUSTYFRI"
+
+/// The day of puzzle #0, the same day the original game started counting from.
+pub fn epoch() -> NaiveDate {
+    NaiveDate::from_ymd_opt(2021, 6, 19).unwrap()
+}
+
+/// The puzzle number for `date`, or `None` for dates before the epoch.
+pub fn puzzle_number(date: NaiveDate) -> Option<u32> {
+    let days = date.signed_duration_since(epoch()).num_days();
+    u32::try_from(days).ok()
+}
+
+/// The answer everyone gets for puzzle `number`. The answers are walked in a fixed shuffled order
+/// so the list only starts repeating after every word has been used once.
+pub fn answer(answers: &[String], number: u32) -> Option<&String> {
+    if answers.is_empty() {
+        return None;
+    }
+    let mut order: Vec<usize> = (0..answers.len()).collect();
+    let mut rng = ChaCha8Rng::seed_from_u64(SHUFFLE_SEED);
+    // a hand-rolled Fisher-Yates, so the order can't change under us with a new `rand` release
+    for i in (1..order.len()).rev() {
+        let j = (rng.next_u64() % (i as u64 + 1)) as usize;
+        order.swap(i, j);
+    }
+    Some(&answers[order[number as usize % order.len()]])
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::daily::{answer, epoch, puzzle_number};
+    use chrono::NaiveDate;
+
+    fn words() -> Vec<String> {
+        ["CRANE", "RUSTY", "SLATE", "TOAST", "WHALE"]
+            .iter()
+            .map(|w| w.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn numbers_from_the_epoch() {
+        assert_eq!(puzzle_number(epoch()), Some(0));
+        assert_eq!(puzzle_number(NaiveDate::from_ymd_opt(2022, 8, 5).unwrap()), Some(412));
+        assert_eq!(puzzle_number(NaiveDate::from_ymd_opt(2021, 6, 18).unwrap()), None);
+    }
+
+    #[test]
+    fn same_answer_for_everyone() {
+        let words = words();
+        assert_eq!(answer(&words, 412), answer(&words.clone(), 412));
+        assert_eq!(answer(&words, 3), answer(&words, 8));
+        assert_eq!(answer(&[], 3), None);
+    }
+
+    #[test]
+    fn uses_every_word_before_repeating() {
+        let words = words();
+        let mut seen: Vec<&String> = (0..5).map(|n| answer(&words, n).unwrap()).collect();
+        seen.sort();
+        seen.dedup();
+        assert_eq!(seen.len(), 5);
+    }
+}
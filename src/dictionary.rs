@@ -1,55 +1,302 @@
 use crate::utils;
-use std::collections::HashMap;
+use rand::seq::SliceRandom;
+use rand::RngCore;
+use std::collections::{HashMap, HashSet};
+use std::io;
+use std::sync::Arc;
 
-#[derive(Debug, Clone)]
-pub struct ComputerDictionary {
-    words: HashMap<String, bool>,
-}
+/// Common words that make fair secret words, one per line.
+pub const ANSWERS_PATH: &str = "./data/answers.txt";
+/// Every word a player is allowed to guess, one per line.
+pub const DICTIONARY_PATH: &str = "./data/dictionary.txt";
 
+/// A game draws its secret word from a short list of common answers, but accepts guesses from a
+/// much broader list so players aren't punished for knowing obscure words.
 pub trait DictionaryLike {
-    fn is_a_word(&self, word: &str) -> bool;
+    fn is_allowed_guess(&self, word: &str) -> bool;
+
+    /// Every answer with `length` letters, uppercase.
+    fn answers(&self, length: usize) -> &[String];
+
+    /// A lowercase secret word with `length` letters, or `None` if there aren't any.
+    fn random_answer(&self, length: usize, rng: &mut dyn RngCore) -> Option<String> {
+        self.answers(length).choose(rng).map(|w| w.to_lowercase())
+    }
+}
+
+/// One loaded dictionary shared by every game on a server.
+pub type SharedDictionary = Arc<dyn DictionaryLike + Send + Sync>;
+
+impl<T: DictionaryLike + ?Sized> DictionaryLike for Arc<T> {
+    fn is_allowed_guess(&self, word: &str) -> bool {
+        (**self).is_allowed_guess(word)
+    }
+
+    fn answers(&self, length: usize) -> &[String] {
+        (**self).answers(length)
+    }
+}
+
+/// Groups answers by length, keeping them sorted so the same list always comes out in the same order.
+fn index_answers<I: IntoIterator<Item = String>>(words: I) -> HashMap<usize, Vec<String>> {
+    let mut answers: HashMap<usize, Vec<String>> = HashMap::new();
+    for word in words {
+        let word = word.trim().to_uppercase();
+        if word.is_empty() {
+            continue;
+        }
+        answers.entry(word.chars().count()).or_default().push(word);
+    }
+    for words in answers.values_mut() {
+        words.sort();
+        words.dedup();
+    }
+    answers
+}
+
+/// Words loaded from text files with one word per line, grouped by length so a game only ever
+/// looks at words that could fit on its board.
+#[derive(Debug, Clone)]
+pub struct ComputerDictionary {
+    answers: HashMap<usize, Vec<String>>,
+    words: HashMap<usize, HashSet<String>>,
 }
 
 impl ComputerDictionary {
-    pub fn new(filename: &str) -> Self {
-        let mut words = HashMap::new();
-        let lines = utils::read_lines(filename).expect("cant read text file");
-        for line in lines {
-            if let Ok(word) = line {
-                words.insert(word, true);
+    pub fn new(answers_filename: &str, filename: &str) -> io::Result<Self> {
+        let answers = index_answers(utils::read_lines(answers_filename)?.collect::<io::Result<Vec<String>>>()?);
+        let mut words: HashMap<usize, HashSet<String>> = HashMap::new();
+        for line in utils::read_lines(filename)? {
+            let word = line?.trim().to_uppercase();
+            if word.is_empty() {
+                continue;
             }
+            words.entry(word.chars().count()).or_default().insert(word);
         }
-        ComputerDictionary { words: HashMap::new() }
-    }
-    pub fn list(&self) {
-        for w in &self.words {
-            println!("{}", w.0);
+        // every answer has to be guessable, even if the broader list is missing it
+        for (length, answers) in &answers {
+            words.entry(*length).or_default().extend(answers.iter().cloned());
         }
+        Ok(ComputerDictionary { answers, words })
+    }
+
+    #[cfg(test)]
+    pub fn len(&self) -> usize {
+        self.words.values().map(|w| w.len()).sum()
+    }
+
+    #[cfg(test)]
+    pub fn is_empty(&self) -> bool {
+        self.words.values().all(|w| w.is_empty())
+    }
+
+    /// Every word length there are words for, shortest first.
+    pub fn lengths(&self) -> Vec<usize> {
+        let mut lengths: Vec<usize> = self.words.keys().copied().collect();
+        lengths.sort();
+        lengths
+    }
+
+    pub fn words_of_length(&self, length: usize) -> impl Iterator<Item = &String> {
+        self.words.get(&length).into_iter().flatten()
     }
 }
 
 impl DictionaryLike for ComputerDictionary {
-    fn is_a_word(&self, a: &str) -> bool {
-        println!("{}", a);
+    fn is_allowed_guess(&self, a: &str) -> bool {
         let clean = a.trim().to_uppercase();
-        let value = &self.words.contains_key(&clean);
-        value.clone()
+        self.words
+            .get(&clean.chars().count())
+            .is_some_and(|words| words.contains(&clean))
+    }
+
+    fn answers(&self, length: usize) -> &[String] {
+        self.answers.get(&length).map_or(&[], |w| w.as_slice())
     }
 }
 
+/// Every allowed five letter guess, packed by `build.rs` into 3 bytes each and sorted. About 27KB,
+/// so it stays well inside what's reasonable for a size-optimised wasm module, where the full
+/// dictionary would be over 1.7MB.
+#[cfg(any(target_arch = "wasm32", test))]
+static PACKED_WORDS: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/words5.bin"));
+#[cfg(any(target_arch = "wasm32", test))]
+const PACKED_WORD_LENGTH: usize = 5;
+#[cfg(any(target_arch = "wasm32", test))]
+const PACKED_WORD_BYTES: usize = 3;
+
+/// The same packing as `build.rs`: the letters read as a base 26 number.
+#[cfg(any(target_arch = "wasm32", test))]
+fn pack(word: &str) -> Option<u32> {
+    if word.len() != PACKED_WORD_LENGTH || !word.bytes().all(|b| b.is_ascii_uppercase()) {
+        return None;
+    }
+    Some(word.bytes().fold(0, |n, b| n * 26 + (b - b'A') as u32))
+}
+
+#[cfg(any(target_arch = "wasm32", test))]
+fn unpack(bytes: &[u8]) -> u32 {
+    u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]])
+}
+
+/// The dictionary for the browser, where there's no file system. Answers come from the embedded
+/// answer list; five letter guesses are checked against the packed word list and guesses of other
+/// lengths only against the answers.
+#[cfg(any(target_arch = "wasm32", test))]
 #[derive(Debug, Clone)]
 pub struct WebDictionary {
-    words: HashMap<String, bool>,
+    answers: HashMap<usize, Vec<String>>,
 }
 
+#[cfg(any(target_arch = "wasm32", test))]
 impl WebDictionary {
     pub fn new() -> Self {
-        WebDictionary { words: HashMap::new() }
+        let answers = include_str!("../data/answers.txt").lines().map(|w| w.to_string());
+        WebDictionary {
+            answers: index_answers(answers),
+        }
     }
 }
 
+#[cfg(any(target_arch = "wasm32", test))]
+impl Default for WebDictionary {
+    fn default() -> Self {
+        WebDictionary::new()
+    }
+}
+
+#[cfg(any(target_arch = "wasm32", test))]
 impl DictionaryLike for WebDictionary {
-    fn is_a_word(&self, a: &str) -> bool {
-        true
+    fn is_allowed_guess(&self, a: &str) -> bool {
+        let clean = a.trim().to_uppercase();
+        if clean.chars().count() != PACKED_WORD_LENGTH {
+            return self.answers(clean.chars().count()).binary_search(&clean).is_ok();
+        }
+        let target = match pack(&clean) {
+            Some(target) => target,
+            None => return false,
+        };
+        let (mut low, mut high) = (0, PACKED_WORDS.len() / PACKED_WORD_BYTES);
+        while low < high {
+            let mid = (low + high) / 2;
+            let start = mid * PACKED_WORD_BYTES;
+            let value = unpack(&PACKED_WORDS[start..start + PACKED_WORD_BYTES]);
+            if value == target {
+                return true;
+            } else if value < target {
+                low = mid + 1;
+            } else {
+                high = mid;
+            }
+        }
+        false
+    }
+
+    fn answers(&self, length: usize) -> &[String] {
+        self.answers.get(&length).map_or(&[], |w| w.as_slice())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dictionary::{
+        ComputerDictionary, DictionaryLike, WebDictionary, ANSWERS_PATH, DICTIONARY_PATH, PACKED_WORDS,
+    };
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn dictionary() -> ComputerDictionary {
+        ComputerDictionary::new(ANSWERS_PATH, DICTIONARY_PATH).unwrap()
+    }
+
+    #[test]
+    fn loads_the_word_file() {
+        let dictionary = dictionary();
+        assert!(dictionary.len() > 100_000);
+        assert!(!dictionary.is_empty());
+        assert!(dictionary.words_of_length(5).all(|w| w.len() == 5));
+    }
+
+    #[test]
+    fn case_insensitive() {
+        let dictionary = dictionary();
+        assert!(dictionary.is_allowed_guess("rusty"));
+        assert!(dictionary.is_allowed_guess("RUSTY"));
+        assert!(dictionary.is_allowed_guess("RuStY"));
+        assert!(dictionary.is_allowed_guess(" rusty\n"));
+    }
+
+    #[test]
+    fn rejects_non_words() {
+        let dictionary = dictionary();
+        assert!(!dictionary.is_allowed_guess("abcde"));
+        assert!(!dictionary.is_allowed_guess("rustyy"));
+        assert!(!dictionary.is_allowed_guess(""));
+    }
+
+    #[test]
+    fn missing_file() {
+        let err = ComputerDictionary::new("./data/answers.txt", "./data/missing.txt").unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn answers_are_common_words() {
+        let dictionary = dictionary();
+        for length in 4..=7 {
+            assert!(dictionary.answers(length).len() > 400);
+            for answer in dictionary.answers(length) {
+                assert_eq!(answer.len(), length);
+                assert!(dictionary.is_allowed_guess(answer));
+            }
+        }
+        assert!(!dictionary.answers(5).contains(&"AAHED".to_string()));
+        assert!(dictionary.answers(1).is_empty());
+    }
+
+    #[test]
+    fn random_answer() {
+        let dictionary = dictionary();
+        let mut rng = StdRng::seed_from_u64(7);
+        let answer = dictionary.random_answer(5, &mut rng).unwrap();
+        assert_eq!(answer.len(), 5);
+        assert_eq!(answer, answer.to_lowercase());
+        assert!(dictionary.answers(5).contains(&answer.to_uppercase()));
+        assert_eq!(dictionary.random_answer(20, &mut rng), None);
+    }
+
+    #[test]
+    fn web_answers_match() {
+        let dictionary = dictionary();
+        let web = WebDictionary::new();
+        for length in 4..=7 {
+            assert_eq!(web.answers(length), dictionary.answers(length));
+        }
+    }
+
+    #[test]
+    fn web_dictionary_checks_guesses() {
+        let web = WebDictionary::new();
+        assert!(web.is_allowed_guess("rusty"));
+        assert!(web.is_allowed_guess("AAHED"));
+        assert!(!web.is_allowed_guess("abcde"));
+        assert!(!web.is_allowed_guess("ru5ty"));
+        assert!(web.is_allowed_guess("puzzle"));
+        assert!(!web.is_allowed_guess("aahing"));
+    }
+
+    #[test]
+    fn web_dictionary_has_every_five_letter_word() {
+        let dictionary = dictionary();
+        let web = WebDictionary::new();
+        assert_eq!(PACKED_WORDS.len() / 3, dictionary.words_of_length(5).count());
+        for word in dictionary.words_of_length(5) {
+            assert!(web.is_allowed_guess(word), "{} is missing", word);
+        }
+    }
+
+    #[test]
+    fn web_dictionary_size_budget() {
+        assert!(PACKED_WORDS.len() <= 32 * 1024);
     }
 }
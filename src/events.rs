@@ -0,0 +1,114 @@
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use crate::signature;
+
+/// Set on an event Slack is sending again, to how many times it has tried.
+pub const RETRY_HEADER: &str = "X-Slack-Retry-Num";
+
+/// What an endpoint answers with.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Response {
+    pub status: u16,
+    pub body: String,
+}
+
+impl Response {
+    pub fn ok<S: Into<String>>(body: S) -> Self {
+        Response {
+            status: 200,
+            body: body.into(),
+        }
+    }
+
+    pub fn bad_request<S: Into<String>>(body: S) -> Self {
+        Response {
+            status: 400,
+            body: body.into(),
+        }
+    }
+
+    pub fn unauthorized<S: Into<String>>(body: S) -> Self {
+        Response {
+            status: 401,
+            body: body.into(),
+        }
+    }
+
+    pub fn not_found<S: Into<String>>(body: S) -> Self {
+        Response {
+            status: 404,
+            body: body.into(),
+        }
+    }
+}
+
+/// Checks that a request came from Slack, answering 401 if it didn't. `now` is in seconds since the epoch.
+pub fn authenticate<'a>(
+    secret: &str,
+    headers: &HashMap<String, String>,
+    body: Option<&'a str>,
+    now: i64,
+) -> Result<&'a str, Response> {
+    let body = body.unwrap_or_default();
+    match signature::verify(secret, headers, body, now) {
+        Ok(()) => Ok(body),
+        Err(e) => Err(Response::unauthorized(e.to_string())),
+    }
+}
+
+/// The JSON body of an authenticated Events API request, or 400 if it isn't JSON.
+pub fn parse(secret: &str, headers: &HashMap<String, String>, body: Option<&str>, now: i64) -> Result<Value, Response> {
+    let body = authenticate(secret, headers, body, now)?;
+    serde_json::from_str(body).map_err(|e| Response::bad_request(format!("invalid JSON: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::events::parse;
+    use crate::signature::sign;
+    use crate::signature::tests::{Fixture, MESSAGE_EVENT, SLASH_COMMAND, URL_VERIFICATION};
+
+    #[test]
+    fn parses_recorded_events() {
+        let fixture = Fixture::load(MESSAGE_EVENT);
+        let v = parse(&fixture.secret, &fixture.headers(), Some(&fixture.body), fixture.now()).unwrap();
+        assert_eq!(v["event"]["text"], "crane");
+        assert_eq!(v["event"]["channel"], "C2147483705");
+
+        let fixture = Fixture::load(URL_VERIFICATION);
+        let v = parse(&fixture.secret, &fixture.headers(), Some(&fixture.body), fixture.now()).unwrap();
+        assert_eq!(v["type"], "url_verification");
+    }
+
+    #[test]
+    fn forged_requests_are_unauthorized() {
+        let fixture = Fixture::load(MESSAGE_EVENT);
+        let response = parse("not the secret", &fixture.headers(), Some(&fixture.body), fixture.now()).unwrap_err();
+        assert_eq!(response.status, 401);
+
+        let response = parse(&fixture.secret, &Default::default(), Some(&fixture.body), fixture.now()).unwrap_err();
+        assert_eq!(response.status, 401);
+
+        let response = parse(&fixture.secret, &fixture.headers(), None, fixture.now()).unwrap_err();
+        assert_eq!(response.status, 401);
+    }
+
+    #[test]
+    fn signed_garbage_is_a_bad_request() {
+        // correctly signed, but a slash command is form encoded rather than JSON
+        let fixture = Fixture::load(SLASH_COMMAND);
+        let response = parse(&fixture.secret, &fixture.headers(), Some(&fixture.body), fixture.now()).unwrap_err();
+        assert_eq!(response.status, 400);
+
+        let mut headers = fixture.headers();
+        let body = "{\"event\":";
+        headers.insert(
+            "X-Slack-Signature".to_string(),
+            sign(&fixture.secret, &fixture.timestamp, body),
+        );
+        let response = parse(&fixture.secret, &headers, Some(body), fixture.now()).unwrap_err();
+        assert_eq!(response.status, 400);
+    }
+}
@@ -0,0 +1,64 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::board::{ordinal, Board, Cell};
+use crate::solver;
+
+/// Help for a stuck player. Each hint asked for in a game gives away a little more than the last.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Hint {
+    RemainingAnswers(usize),
+    PresentLetter(char),
+    CorrectPosition { position: usize, letter: char },
+    SuggestedGuess(String),
+}
+
+impl fmt::Display for Hint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Hint::RemainingAnswers(1) => write!(f, "There is only 1 possible answer left."),
+            Hint::RemainingAnswers(n) => write!(f, "There are {} possible answers left.", n),
+            Hint::PresentLetter(letter) => write!(f, "The word contains {}.", letter.to_ascii_uppercase()),
+            Hint::CorrectPosition { position, letter } => write!(
+                f,
+                "The {} letter is {}.",
+                ordinal(position + 1),
+                letter.to_ascii_uppercase()
+            ),
+            Hint::SuggestedGuess(word) => write!(f, "Try {}.", word.to_uppercase()),
+        }
+    }
+}
+
+/// The hint to give for the `level`th time a player asks (starting at 0). Levels whose hint would
+/// reveal nothing new, like a letter that's already yellow, fall through to the next one.
+pub fn next_hint(secret: &str, board: &Board, level: usize) -> Option<Hint> {
+    let revealed = |letter: char| {
+        board
+            .rows
+            .iter()
+            .flatten()
+            .any(|c| *c == Cell::Green(letter) || *c == Cell::Yellow(letter))
+    };
+    if level == 0 {
+        return Some(Hint::RemainingAnswers(solver::candidates(board).len()));
+    }
+    if level <= 1 {
+        if let Some(letter) = secret.chars().find(|&c| !revealed(c)) {
+            return Some(Hint::PresentLetter(letter));
+        }
+    }
+    if level <= 2 {
+        let unsolved = secret
+            .chars()
+            .enumerate()
+            .find(|&(position, _)| !board.rows.iter().any(|r| matches!(r[position], Cell::Green(_))));
+        if let Some((position, letter)) = unsolved {
+            return Some(Hint::CorrectPosition { position, letter });
+        }
+    }
+    solver::suggest_candidates(board, 1)
+        .into_iter()
+        .next()
+        .map(|s| Hint::SuggestedGuess(s.word))
+}
@@ -0,0 +1,175 @@
+use std::collections::HashMap;
+use std::io::{self, Error, Write};
+
+use crate::board::Cell;
+use crate::theme::Theme;
+#[cfg(target_arch = "wasm32")]
+use crate::traits::WebComponent;
+
+#[cfg(not(target_arch = "wasm32"))]
+use crossterm::{cursor, queue, style};
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::JsValue;
+
+pub const KEYBOARD_ROWS: [&str; 3] = ["qwertyuiop", "asdfghjkl", "zxcvbnm"];
+
+/// What the guesses so far say about a letter. Ordered so the most informative status wins: a
+/// letter that was green anywhere stays green even if a later guess has it yellow or gray.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LetterStatus {
+    Unknown,
+    Absent,
+    Present,
+    Correct,
+}
+
+impl LetterStatus {
+    fn from_cell(cell: &Cell) -> Option<(char, LetterStatus)> {
+        match cell {
+            Cell::Green(c) => Some((*c, LetterStatus::Correct)),
+            Cell::Yellow(c) => Some((*c, LetterStatus::Present)),
+            Cell::Gray(c) => Some((*c, LetterStatus::Absent)),
+            Cell::Empty => None,
+        }
+    }
+}
+
+/// The status of every letter, for drawing an on-screen keyboard.
+#[derive(Debug, Clone, Default)]
+pub struct Keyboard {
+    letters: HashMap<char, LetterStatus>,
+}
+
+impl Keyboard {
+    pub fn new() -> Self {
+        Keyboard::default()
+    }
+
+    pub fn update(&mut self, row: &[Cell]) {
+        for (letter, status) in row.iter().filter_map(LetterStatus::from_cell) {
+            let current = self.letters.entry(letter).or_insert(LetterStatus::Unknown);
+            *current = (*current).max(status);
+        }
+    }
+
+    pub fn status(&self, letter: char) -> LetterStatus {
+        *self
+            .letters
+            .get(&letter.to_ascii_lowercase())
+            .unwrap_or(&LetterStatus::Unknown)
+    }
+
+    pub fn slack(&self, theme: Theme) -> String {
+        let mut response = "".to_string();
+        for row in KEYBOARD_ROWS {
+            let keys: Vec<String> = row
+                .chars()
+                .map(|c| {
+                    let letter = c.to_ascii_uppercase();
+                    let status = self.status(c);
+                    match theme.key_emoji(status) {
+                        Some(emoji) => format!("{}{}", emoji, letter),
+                        None if status == LetterStatus::Absent => format!("~{}~", letter),
+                        None => letter.to_string(),
+                    }
+                })
+                .collect();
+            response.push_str(&keys.join(" "));
+            response.push('\n');
+        }
+        response
+    }
+
+    /// Draws the keyboard starting at terminal row `top`, each row indented like a real keyboard.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn print(&self, top: u16, theme: Theme) -> Result<(), Error> {
+        self.draw(&mut io::stdout(), 0, top, theme)
+    }
+
+    /// Like `print`, but to any terminal and with the left edge at column `left`.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn draw<W: Write>(&self, out: &mut W, left: u16, top: u16, theme: Theme) -> Result<(), Error> {
+        for (idx, row) in KEYBOARD_ROWS.iter().enumerate() {
+            queue!(
+                out,
+                style::ResetColor,
+                cursor::MoveTo(left + idx as u16 * 2, top + idx as u16)
+            )?;
+            for c in row.chars() {
+                let (background, foreground) = theme.terminal_key(self.status(c));
+                queue!(
+                    out,
+                    style::SetBackgroundColor(background),
+                    style::SetForegroundColor(foreground),
+                    style::Print(format!(" {} ", c.to_ascii_uppercase())),
+                    style::ResetColor,
+                    style::Print(" ")
+                )?;
+            }
+        }
+        queue!(out, style::ResetColor)?;
+        Ok(())
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+impl WebComponent for Keyboard {
+    fn to_dom(&self, document: &web_sys::Document, theme: Theme) -> Result<web_sys::Element, JsValue> {
+        let key_style = "display: flex; align-items: center; justify-content: center; font-size: 14px; margin: 3px; width: 30px; height: 40px; border-radius: 4px;";
+        let container: web_sys::Element = document.create_element("div")?;
+        container.set_attribute("style", "display: flex; flex-direction: column; align-items: center")?;
+        for row in KEYBOARD_ROWS {
+            let row_div: web_sys::Element = document.create_element("div")?;
+            row_div.set_attribute("style", "display: flex; flex-direction: row")?;
+            for c in row.chars() {
+                let key_div: web_sys::Element = document.create_element("div")?;
+                let colour = theme.css_key(self.status(c)).style();
+                key_div.set_attribute("style", &format!("{} {}", key_style, colour))?;
+                key_div.set_text_content(Some(&c.to_ascii_uppercase().to_string()));
+                row_div.append_child(&key_div)?;
+            }
+            container.append_child(&row_div)?;
+        }
+        Ok(container)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::Cell;
+    use crate::keyboard::{Keyboard, LetterStatus};
+    use crate::theme::Theme;
+
+    #[test]
+    fn unknown_until_guessed() {
+        let keyboard = Keyboard::new();
+        assert_eq!(keyboard.status('a'), LetterStatus::Unknown);
+    }
+
+    #[test]
+    fn correct_dominates() {
+        let mut keyboard = Keyboard::new();
+        keyboard.update(&[Cell::Yellow('r'), Cell::Gray('x'), Cell::Gray('r')]);
+        assert_eq!(keyboard.status('r'), LetterStatus::Present);
+        assert_eq!(keyboard.status('X'), LetterStatus::Absent);
+        keyboard.update(&[Cell::Green('r'), Cell::Empty, Cell::Yellow('x')]);
+        assert_eq!(keyboard.status('r'), LetterStatus::Correct);
+        keyboard.update(&[Cell::Gray('r'), Cell::Gray('x'), Cell::Empty]);
+        assert_eq!(keyboard.status('r'), LetterStatus::Correct);
+        assert_eq!(keyboard.status('x'), LetterStatus::Present);
+    }
+
+    #[test]
+    fn slack_keyboard() {
+        let mut keyboard = Keyboard::new();
+        keyboard.update(&[Cell::Green('q'), Cell::Yellow('w'), Cell::Gray('e')]);
+        assert_eq!(
+            keyboard.slack(Theme::Standard).lines().next(),
+            Some("🟩Q 🟨W ~E~ R T Y U I O P")
+        );
+        assert_eq!(
+            keyboard.slack(Theme::HighContrast).lines().next(),
+            Some("🟧Q 🟦W ~E~ R T Y U I O P")
+        );
+    }
+}
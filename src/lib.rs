@@ -1,16 +1,25 @@
-mod board;
-mod dictionary;
+pub mod board;
+mod daily;
+pub mod dictionary;
+pub mod hint;
+pub mod keyboard;
+pub mod solver;
+pub mod stats;
+pub mod theme;
+#[cfg(target_arch = "wasm32")]
 mod traits;
 mod utils;
-mod wasm_utils;
-use std::cell::RefCell;
-use std::error;
+#[cfg(target_arch = "wasm32")]
+use std::cell::{Cell, RefCell};
+#[cfg(target_arch = "wasm32")]
 use std::rc::Rc;
 
+#[cfg(target_arch = "wasm32")]
 use traits::WebComponent;
+#[cfg(target_arch = "wasm32")]
 use wasm_bindgen::{prelude::*, JsCast};
-use web_sys::{console, Event};
-use web_sys::{Document, Element};
+#[cfg(target_arch = "wasm32")]
+use web_sys::{Document, Element, Event};
 
 // When the `wee_alloc` feature is enabled, use `wee_alloc` as the global
 // allocator.
@@ -18,31 +27,149 @@ use web_sys::{Document, Element};
 #[global_allocator]
 static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
 
+/// Where the browser remembers the player's theme.
+#[cfg(target_arch = "wasm32")]
+const THEME_KEY: &str = "wordle-theme";
+/// Where the browser keeps the player's finished games.
+#[cfg(target_arch = "wasm32")]
+const STATS_KEY: &str = "wordle-stats";
+
 // Called by our JS entry point to run the example
+#[cfg(target_arch = "wasm32")]
 #[wasm_bindgen(start)]
 pub fn run() -> Result<(), JsValue> {
     // proper errors in console
     console_error_panic_hook::set_once();
 
-    let board = Rc::new(RefCell::new(board::Board::new_wasm("rusty".to_string())));
-    let mut our_board = Rc::clone(&board); // 0xff00ab11
+    let dictionary = Box::new(dictionary::WebDictionary::new());
+    let today = chrono::Local::now().date_naive();
+    let new_board = board::Board::daily(today, board::BoardConfig::default(), dictionary)
+        .ok_or_else(|| JsValue::from_str("no puzzle for today"))?;
+    let board = Rc::new(RefCell::new(new_board));
+    let our_board = Rc::clone(&board); // 0xff00ab11
 
     // Use `web_sys`'s global `window` function to get a handle on the global
     // window object.
     let window = web_sys::window().expect("no global `window` exists");
     let document = window.document().expect("should have a document on window");
     let body = document.body().expect("document should have a body");
+    let theme = Rc::new(Cell::new(saved_theme(&window)));
+    set_page_theme(&body, theme.get())?;
+
+    let title: Element = document.create_element("h2")?;
+    if let Some(puzzle) = board.borrow().puzzle_number() {
+        title.set_text_content(Some(&format!("Wordle {}", puzzle)));
+    }
 
     // add text input for guess
     let input: Element = document.create_element("input")?;
     input.set_attribute("placeholder", "guess a word")?;
 
+    // hard mode toggle, which the board only accepts before the first guess
+    let hard_mode_label: Element = document.create_element("label")?;
+    let hard_mode = document
+        .create_element("input")?
+        .dyn_into::<web_sys::HtmlInputElement>()?;
+    hard_mode.set_type("checkbox");
+    hard_mode_label.append_child(&hard_mode)?;
+    let hard_mode_text: Element = document.create_element("span")?;
+    hard_mode_text.set_text_content(Some("Hard mode"));
+    hard_mode_label.append_child(&hard_mode_text)?;
+
+    // asking for a hint shows it in the status line
+    let hint_button: Element = document.create_element("button")?;
+    hint_button.set_text_content(Some("Hint"));
+
+    // show errors, guesses left and the result of the game
+    let status: Element = document.create_element("p")?;
+    status.set_attribute("style", "white-space: pre-line")?;
+    status.set_text_content(Some(&status_text(&board.borrow(), theme.get())));
+
+    // played, streaks and the guess distribution, once there's a finished game to count
+    let stats = Rc::new(RefCell::new(saved_stats(&window)));
+    let stats_container: Element = document.create_element("div")?;
+    show_stats(&stats_container, &document, &stats.borrow(), theme.get())?;
+
     let container = Rc::new(RefCell::new(document.create_element("div").unwrap()));
-    let dom_board = our_board.borrow().to_dom(&document)?;
+    let dom_board = our_board.borrow().to_dom(&document, theme.get())?;
     container.borrow_mut().append_child(&dom_board)?;
 
+    // colours, remembered by the browser for next time
+    let theme_select = document
+        .create_element("select")?
+        .dyn_into::<web_sys::HtmlSelectElement>()?;
+    for option in theme::Theme::ALL {
+        let option_element: Element = document.create_element("option")?;
+        option_element.set_attribute("value", option.name())?;
+        option_element.set_text_content(Some(option.name()));
+        theme_select.append_child(&option_element)?;
+    }
+    theme_select.set_value(theme.get().name());
+
     let board_ref = Rc::clone(&board);
     let container_ref = Rc::clone(&container);
+    let status_ref = status.clone();
+    let theme_ref = Rc::clone(&theme);
+    let body_ref = body.clone();
+    let document_ref = document.clone();
+    let stats_ref = Rc::clone(&stats);
+    let stats_container_ref = stats_container.clone();
+    let theme_cb = Closure::wrap(Box::new(move |e: Event| {
+        let select = e
+            .current_target()
+            .unwrap()
+            .dyn_into::<web_sys::HtmlSelectElement>()
+            .unwrap();
+        let chosen: theme::Theme = select.value().parse().unwrap_or_default();
+        theme_ref.set(chosen);
+        if let Some(storage) = web_sys::window().and_then(|w| w.local_storage().ok().flatten()) {
+            // private browsing can refuse, which only means it's forgotten next time
+            let _ = storage.set_item(THEME_KEY, chosen.name());
+        }
+        set_page_theme(&body_ref, chosen).unwrap();
+        let dom_board = board_ref.borrow().to_dom(&document_ref, chosen).unwrap();
+        container_ref.borrow_mut().set_text_content("".into());
+        container_ref.borrow_mut().append_child(&dom_board).unwrap();
+        if board_ref.borrow().is_over() {
+            status_ref.set_text_content(Some(&status_text(&board_ref.borrow(), chosen)));
+        }
+        show_stats(&stats_container_ref, &document_ref, &stats_ref.borrow(), chosen).unwrap();
+    }) as Box<dyn FnMut(_)>);
+    theme_select.add_event_listener_with_callback("change", theme_cb.as_ref().unchecked_ref())?;
+    theme_cb.forget();
+
+    let board_ref = Rc::clone(&board);
+    let status_ref = status.clone();
+    let hard_mode_cb = Closure::wrap(Box::new(move |e: Event| {
+        let checkbox = e
+            .current_target()
+            .unwrap()
+            .dyn_into::<web_sys::HtmlInputElement>()
+            .unwrap();
+        if !board_ref.borrow_mut().set_hard_mode(checkbox.checked()) {
+            checkbox.set_checked(false);
+            status_ref.set_text_content(Some("Hard mode can only be turned on before the first guess."));
+        }
+    }) as Box<dyn FnMut(_)>);
+    hard_mode.add_event_listener_with_callback("change", hard_mode_cb.as_ref().unchecked_ref())?;
+    hard_mode_cb.forget();
+
+    let board_ref = Rc::clone(&board);
+    let status_ref = status.clone();
+    let hint_cb = Closure::wrap(Box::new(move |_: Event| {
+        if let Some(hint) = board_ref.borrow_mut().hint() {
+            status_ref.set_text_content(Some(&hint.to_string()));
+        }
+    }) as Box<dyn FnMut(_)>);
+    hint_button.add_event_listener_with_callback("click", hint_cb.as_ref().unchecked_ref())?;
+    hint_cb.forget();
+
+    let board_ref = Rc::clone(&board);
+    let container_ref = Rc::clone(&container);
+    let status_ref = status.clone();
+    let theme_ref = Rc::clone(&theme);
+    let stats_ref = Rc::clone(&stats);
+    let stats_container_ref = stats_container.clone();
     let cb = Closure::wrap(Box::new(move |e: Event| {
         let input = e
             .current_target()
@@ -50,19 +177,108 @@ pub fn run() -> Result<(), JsValue> {
             .dyn_into::<web_sys::HtmlInputElement>()
             .unwrap();
 
-        if input.value().len() == 5 {
-            &board_ref.borrow_mut().guess(&input.value());
+        if input.value().len() == board_ref.borrow().config().word_length {
+            if let Err(e) = board_ref.borrow_mut().guess(&input.value()) {
+                let message = match e {
+                    board::GuessError::NotInDictionary(_) => "Not in word list".to_string(),
+                    e => e.to_string(),
+                };
+                status_ref.set_text_content(Some(&message));
+                return;
+            }
             let document = window.document().expect("should have a document on window");
-            let dom_board2 = board.borrow().to_dom(&document).unwrap();
+            let dom_board2 = board.borrow().to_dom(&document, theme_ref.get()).unwrap();
             container_ref.borrow_mut().set_text_content("".into());
             container_ref.borrow_mut().append_child(&dom_board2).unwrap();
+            status_ref.set_text_content(Some(&status_text(&board.borrow(), theme_ref.get())));
+            input.set_value("");
+            if board.borrow().is_over() {
+                input.set_disabled(true);
+                if let Some(game) = stats::GameRecord::from_board(&board.borrow()) {
+                    if stats_ref.borrow_mut().record(game) {
+                        save_stats(&window, &stats_ref.borrow());
+                    }
+                }
+                show_stats(&stats_container_ref, &document, &stats_ref.borrow(), theme_ref.get()).unwrap();
+            }
         }
     }) as Box<dyn FnMut(_)>);
-    input.add_event_listener_with_callback("input", &cb.as_ref().unchecked_ref())?;
+    input.add_event_listener_with_callback("input", cb.as_ref().unchecked_ref())?;
     cb.forget();
 
+    body.append_child(&title)?;
     body.append_child(&container.borrow())?;
     body.append_child(&input)?;
+    body.append_child(&hint_button)?;
+    body.append_child(&hard_mode_label)?;
+    body.append_child(&theme_select)?;
+    body.append_child(&status)?;
+    body.append_child(&stats_container)?;
+
+    Ok(())
+}
 
+/// The theme picked last time in this browser, if it can remember one.
+#[cfg(target_arch = "wasm32")]
+fn saved_theme(window: &web_sys::Window) -> theme::Theme {
+    window
+        .local_storage()
+        .ok()
+        .flatten()
+        .and_then(|storage| storage.get_item(THEME_KEY).ok().flatten())
+        .and_then(|name| name.parse().ok())
+        .unwrap_or_default()
+}
+
+/// Every game finished in this browser, or none if it can't remember them.
+#[cfg(target_arch = "wasm32")]
+fn saved_stats(window: &web_sys::Window) -> stats::Stats {
+    window
+        .local_storage()
+        .ok()
+        .flatten()
+        .and_then(|storage| storage.get_item(STATS_KEY).ok().flatten())
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+#[cfg(target_arch = "wasm32")]
+fn save_stats(window: &web_sys::Window, stats: &stats::Stats) {
+    if let (Some(storage), Ok(json)) = (window.local_storage().ok().flatten(), serde_json::to_string(stats)) {
+        // private browsing can refuse, which only means it's forgotten next time
+        let _ = storage.set_item(STATS_KEY, &json);
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn show_stats(
+    container: &Element,
+    document: &Document,
+    stats: &stats::Stats,
+    theme: theme::Theme,
+) -> Result<(), JsValue> {
+    container.set_text_content(None);
+    let summary = stats.summary();
+    if summary.played > 0 {
+        let dom_summary = summary.to_dom(document, theme)?;
+        container.append_child(&dom_summary)?;
+    }
     Ok(())
 }
+
+#[cfg(target_arch = "wasm32")]
+fn set_page_theme(body: &web_sys::HtmlElement, theme: theme::Theme) -> Result<(), JsValue> {
+    let page = theme.css_page();
+    body.set_attribute(
+        "style",
+        &format!("background-color: {}; color: {};", page.background, page.text),
+    )
+}
+
+#[cfg(target_arch = "wasm32")]
+fn status_text(board: &board::Board, theme: theme::Theme) -> String {
+    match board.result_message() {
+        Some(result) => format!("{}\n\n{}", result, board.share(theme)),
+        None => format!("{} guesses left", board.remaining_guesses()),
+    }
+}
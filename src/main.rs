@@ -1,82 +1,415 @@
+mod animation;
+mod blocks;
 mod board;
+mod bot;
+mod cli;
+mod command;
+mod config;
+mod daily;
 mod dictionary;
+mod events;
+mod hint;
+mod keyboard;
+mod schedule;
+mod server;
+mod session;
+mod signature;
 mod slack;
+mod solver;
+mod stats;
+mod theme;
+#[cfg(target_arch = "wasm32")]
+mod traits;
+mod tui;
 mod utils;
 
 #[macro_use]
 extern crate lazy_static;
 
-use crate::board::Board;
-use httpserver::HttpServer;
-use serde_json::Value;
+use crate::board::{Board, BoardConfig};
+use crate::bot::Bot;
+use crate::cli::{Cli, CliCommand, DailyArgs, GameArgs};
+use crate::config::Config;
+use crate::dictionary::{ComputerDictionary, DictionaryLike};
+use crate::events::Response;
+use crate::schedule::{Schedule, Scheduler, POLL_INTERVAL};
+use crate::server::HttpServer;
+use crate::session::{Preferences, SessionManager};
+use crate::slack::ReqwestClient;
+use crate::stats::{GameRecord, Stats};
+use crate::theme::Theme;
+use chrono::{Duration, Local, Utc};
+use clap::{CommandFactory, Parser};
+use rand_chacha::rand_core::SeedableRng;
+use rand_chacha::ChaCha8Rng;
 use std::{
-    io::{self, Error, Write},
-    sync::Mutex,
+    env, fs,
+    io::{self, Error, ErrorKind},
+    process,
+    sync::Arc,
+    thread,
 };
 
+/// Where the bot and the terminal keep their in-progress games between restarts.
+const SLACK_SAVE_PATH: &str = "./saves/slack.json";
+const TERMINAL_SAVE_PATH: &str = "./saves/terminal.json";
+const DAILY_SAVE_PATH: &str = "./saves/daily.json";
+const STATS_PATH: &str = "./saves/stats.json";
+const PREFERENCES_PATH: &str = "./saves/preferences.json";
+
 lazy_static! {
-    static ref BOARD: Mutex<Board> = Mutex::new(Board::new("rusty".to_string()));
+    static ref CONFIG: Config = Config::load().unwrap_or_else(|e| {
+        eprintln!("Invalid configuration: {}", e);
+        process::exit(1)
+    });
+    static ref BOT: Bot = slack_bot().expect("Failed to start the Slack bot");
+}
+
+/// Games nobody has touched in this long are dropped.
+const SESSION_IDLE_HOURS: i64 = 24;
+
+/// The word lists from the config file.
+fn dictionary() -> Result<ComputerDictionary, Error> {
+    ComputerDictionary::new(&CONFIG.answers_path, &CONFIG.dictionary_path)
+}
+
+/// Picks up the games the bot was running before it restarted.
+fn slack_bot() -> Result<Bot, Box<dyn std::error::Error>> {
+    let settings = CONFIG.slack()?.clone();
+    let mut sessions = SessionManager::new(
+        settings.session_mode,
+        BoardConfig::default(),
+        Duration::hours(SESSION_IDLE_HOURS),
+        Arc::new(dictionary()?),
+    );
+    match sessions.load(SLACK_SAVE_PATH) {
+        Err(e) if e.kind() != ErrorKind::NotFound => {
+            eprintln!("Couldn't resume the games saved in {}: {}", SLACK_SAVE_PATH, e)
+        }
+        _ => {}
+    }
+    let client = ReqwestClient::with_base_url(&settings.token, &settings.api_url)?;
+    Ok(Bot::new(settings, sessions, Box::new(client))
+        .saving_to(SLACK_SAVE_PATH)
+        .posting_later())
 }
 
 fn slack() {
     let mut server = HttpServer::new();
-    server.get("/", &|req| {
-        return "health_check".to_string();
+    server.get("/", |_| Response::ok("health_check"));
+    server.post("/events", |req| {
+        BOT.handle_event(&req.headers, req.body.as_deref(), Utc::now())
+    });
+    server.post("/wordle", |req| {
+        BOT.handle_command(&req.headers, req.body.as_deref(), Utc::now())
     });
-    server.post("/events", &|req| {
-        let mut board = BOARD.lock().unwrap();
+    server.post("/interactions", |req| {
+        BOT.handle_interaction(&req.headers, req.body.as_deref(), Utc::now())
+    });
+    // fail now rather than on the first request
+    let settings = match CONFIG.slack() {
+        Ok(slack) => slack,
+        Err(e) => {
+            eprintln!("Invalid configuration: {}", e);
+            process::exit(1)
+        }
+    };
+    lazy_static::initialize(&BOT);
+    thread::spawn(|| BOT.post_replies());
+    if let Some(schedule) = settings.daily {
+        thread::spawn(move || daily_puzzle(schedule));
+    }
+    if let Err(e) = server.listen(settings.listen_address) {
+        eprintln!("Couldn't listen on {}: {}", settings.listen_address, e);
+        process::exit(1)
+    }
+}
 
-        // parse the json body
-        let raw_body = req.body.as_ref().unwrap();
-        let v: Value = serde_json::from_str(&raw_body).expect("Can't parse JSON");
+/// Announces the daily puzzle and posts its results on time, while the server answers requests.
+fn daily_puzzle(schedule: Schedule) {
+    let mut scheduler = Scheduler::new(schedule);
+    loop {
+        for task in scheduler.due(Local::now().naive_local()) {
+            BOT.run_task(task);
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+}
 
-        // don't respond if the bot posted the message
-        if v["event"]["user"] == "U03M2JWUMDY" {
-            return "NO_MESSAGE".to_string();
+/// A game with the secret word from `args`, or one drawn from the configured answers. With a seed
+/// it's the same one every time.
+fn new_game(args: &GameArgs) -> Result<Board, Error> {
+    let config = args.config().map_err(|e| Error::new(ErrorKind::InvalidInput, e))?;
+    let dictionary = Box::new(dictionary()?);
+    let board = match &args.secret {
+        Some(secret) if !dictionary.is_allowed_guess(secret) => {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("{} is not in the dictionary", secret),
+            ))
         }
+        Some(secret) => Some(Board::with_dictionary(secret.clone(), config, dictionary)),
+        None => match args.seed {
+            Some(seed) => Board::random(config, dictionary, &mut ChaCha8Rng::seed_from_u64(seed)),
+            None => Board::random(config, dictionary, &mut rand::thread_rng()),
+        },
+    };
+    board.ok_or_else(|| {
+        Error::new(
+            ErrorKind::InvalidInput,
+            format!("there are no {} letter answers", config.word_length),
+        )
+    })
+}
+
+/// The puzzle for `args.date`, today by default, picking it up again if it was left unfinished.
+fn daily_game(args: &DailyArgs) -> Result<(Board, Option<String>), Error> {
+    let date = args.date.unwrap_or_else(|| Local::now().date_naive());
+    let config = args.board.config();
+    if let Some(board) = resume_game(DAILY_SAVE_PATH) {
+        if board.puzzle_number() == daily::puzzle_number(date) && board.config().word_length == config.word_length {
+            return Ok(resumed(board));
+        }
+    }
+    let board = Board::daily(date, config, Box::new(dictionary()?)).ok_or_else(|| {
+        Error::new(
+            ErrorKind::InvalidInput,
+            format!("there's no {} letter puzzle for {}", config.word_length, date),
+        )
+    })?;
+    Ok((board, None))
+}
+
+/// The game saved at `path`, if there is one that isn't finished yet.
+fn resume_game(path: &str) -> Option<Board> {
+    let dictionary = match dictionary() {
+        Ok(dictionary) => Box::new(dictionary),
+        Err(e) => {
+            eprintln!("Couldn't load the dictionary to resume {}: {}", path, e);
+            return None;
+        }
+    };
+    match Board::load_with_dictionary(path, dictionary) {
+        Ok(board) if !board.is_over() => Some(board),
+        Ok(_) => None,
+        Err(e) if e.kind() == ErrorKind::NotFound => None,
+        Err(e) => {
+            eprintln!("Couldn't resume the game saved in {}: {}", path, e);
+            None
+        }
+    }
+}
+
+/// A saved game, with a note of when it was started to greet the player with.
+fn resumed(board: Board) -> (Board, Option<String>) {
+    let greeting = format!(
+        "Resuming your game from {}",
+        board.started_at().with_timezone(&Local).format("%b %-d %H:%M")
+    );
+    (board, Some(greeting))
+}
 
-        // response to text
-        let input = v["event"]["text"].to_string();
-        let trimmed_input = input.trim_matches('"');
-        if input != "" {
-            if input == "null" {
-                return "".to_string();
+/// Saving is best effort: a full disk shouldn't stop anyone from playing.
+fn save_game(board: &Board, path: &str) {
+    if let Err(e) = board.save(path) {
+        eprintln!("Couldn't save the game to {}: {}", path, e);
+    }
+}
+
+/// Every terminal game finished so far, for `wordle stats`.
+fn load_stats() -> Stats {
+    Stats::load(STATS_PATH).unwrap_or_default()
+}
+
+/// Adds `board` to the stats, returning them for showing off.
+fn record_result(board: &Board) -> Stats {
+    let mut stats = load_stats();
+    if let Some(record) = GameRecord::from_board(board) {
+        stats.record(record);
+    }
+    if let Err(e) = stats.save(STATS_PATH) {
+        eprintln!("Couldn't save your stats to {}: {}", STATS_PATH, e);
+    }
+    stats
+}
+
+/// What the terminal player chose last time, or the defaults if they never have.
+fn preferences() -> Preferences {
+    fs::read_to_string(PREFERENCES_PATH)
+        .ok()
+        .and_then(|text| serde_json::from_str(&text).ok())
+        .unwrap_or_default()
+}
+
+/// Remembers `theme` for the next game. Best effort, like saving a game.
+fn save_theme(theme: Theme) {
+    let mut preferences = preferences();
+    preferences.theme = theme;
+    let result = fs::create_dir_all("./saves")
+        .and_then(|_| serde_json::to_string(&preferences).map_err(Error::from))
+        .and_then(|json| fs::write(PREFERENCES_PATH, json));
+    if let Err(e) = result {
+        eprintln!("Couldn't save your theme to {}: {}", PREFERENCES_PATH, e);
+    }
+}
+
+fn play(args: &GameArgs, options: tui::Options) -> Result<(), Error> {
+    let saved = if args.is_new_game() {
+        None
+    } else {
+        resume_game(TERMINAL_SAVE_PATH)
+    };
+    let (board, greeting) = match saved {
+        Some(board) => resumed(board),
+        None => (new_game(args)?, None),
+    };
+    terminal(board, greeting, TERMINAL_SAVE_PATH, options)
+}
+
+/// Plays `board` full screen, saving after every move, and leaves the result and stats behind on
+/// the normal screen.
+fn terminal(board: Board, greeting: Option<String>, save_path: &str, options: tui::Options) -> Result<(), Error> {
+    let board = tui::run(board, greeting, options, |board| save_game(board, save_path))?;
+    if let Some(result) = board.result_message() {
+        let stats = record_result(&board);
+        println!("{}\n\n{}\n", result, board.share(options.theme));
+        stats.summary().draw(&mut io::stdout(), options.theme)?;
+    }
+    Ok(())
+}
+
+/// Lets the solver play, showing each guess and how many answers it left.
+fn solve(args: &GameArgs, theme: Theme) -> Result<(), Error> {
+    let mut board = new_game(args)?;
+    while !board.is_over() {
+        let suggestion = match solver::suggest(&board, 1).into_iter().next() {
+            Some(suggestion) => suggestion,
+            None => {
+                println!("The solver ran out of ideas, the answer isn't one it knows.");
+                break;
             }
-            if !board.dictionary.is_a_word(trimmed_input) {
-                slack::send_slack_message_to_channel(
-                    "rust-wordle-bot",
-                    &format!("{} is not in the dictionary!", trimmed_input.trim()),
-                );
+        };
+        board.guess(&suggestion.word).map_err(|e| Error::other(e.to_string()))?;
+        println!(
+            "{}  {:.2} bits, {} possible answers left",
+            suggestion.word.to_uppercase(),
+            // the last guess tells you nothing, which can come out as -0.00
+            suggestion.entropy.abs(),
+            solver::candidates(&board).len()
+        );
+    }
+    board.print(theme)?;
+    if let Some(result) = board.result_message() {
+        println!("\n{}\n\n{}", result, board.share(theme));
+    }
+    Ok(())
+}
+
+/// The solver's top `count` guesses for the terminal game in progress, or for a first guess if
+/// there isn't one.
+fn suggest(count: usize, daily: bool) -> Result<(), Error> {
+    let path = if daily { DAILY_SAVE_PATH } else { TERMINAL_SAVE_PATH };
+    let board = match resume_game(path) {
+        Some(board) => board,
+        None => {
+            println!("No game in progress, so these are first guesses.");
+            new_game(&GameArgs::default())?
+        }
+    };
+    println!("{} possible answers left", solver::candidates(&board).len());
+    for (i, suggestion) in solver::suggest(&board, count).iter().enumerate() {
+        println!(
+            "{:>3}. {}  {:.2} bits{}",
+            i + 1,
+            suggestion.word.to_uppercase(),
+            suggestion.entropy.abs(),
+            if suggestion.candidate {
+                "  (could be the answer)"
             } else {
-                board.guess(trimmed_input);
-                board.print().expect("Failed to print board to terminal");
-                slack::send_slack_message_to_channel("rust-wordle-bot", &board.slack());
+                ""
             }
-            // TODO: add checks for type of command here
-        }
-        // return the challenge response if needed
-        return v["challenge"].to_string();
-    });
-    server.listen();
+        );
+    }
+    Ok(())
 }
 
-fn terminal() -> Result<(), Error> {
-    loop {
-        let mut input = String::new();
-        print!("\nMake a guess: ");
-        io::stdout().flush()?;
-        io::stdin().read_line(&mut input).expect("failed to read guess");
-        let mut board = BOARD.lock().unwrap();
-        if !board.dictionary.is_a_word(&input) {
-            println!("{} is not in the dictionary!", &input.trim());
-        } else {
-            board.guess(&input);
-            board.print()?;
+fn stats(theme: Theme) -> Result<(), Error> {
+    let summary = load_stats().summary();
+    if summary.played == 0 {
+        println!("No games finished yet.");
+        return Ok(());
+    }
+    summary.draw(&mut io::stdout(), theme)
+}
+
+/// Looks `words` up, or without any, counts the words of each length.
+fn dict(words: &[String]) -> Result<(), Error> {
+    let dictionary = dictionary()?;
+    if words.is_empty() {
+        for length in dictionary.lengths() {
+            println!(
+                "{:>2} letters: {} words, {} answers",
+                length,
+                dictionary.words_of_length(length).count(),
+                dictionary.answers(length).len()
+            );
         }
+        return Ok(());
+    }
+    for word in words {
+        let word = word.to_uppercase();
+        let verdict = if dictionary.answers(word.chars().count()).contains(&word) {
+            "can be guessed and can be the answer"
+        } else if dictionary.is_allowed_guess(&word) {
+            "can be guessed"
+        } else {
+            "isn't in the dictionary"
+        };
+        println!("{} {}", word, verdict);
     }
+    Ok(())
 }
 
 fn main() {
-    // slack();
-    // terminal().expect("Failed to run terminal loop");
+    let cli = Cli::parse();
+    if let Some(CliCommand::Play(args) | CliCommand::Solve(args)) = &cli.command {
+        if let Err(e) = args.config() {
+            Cli::command().error(clap::error::ErrorKind::ArgumentConflict, e).exit();
+        }
+    }
+    // the flag wins over the config file, the same as the environment variable it sets
+    if let Some(path) = &cli.dictionary {
+        env::set_var(config::DICTIONARY.var, path);
+    }
+
+    // a theme given on the command line sticks for next time
+    let theme = match cli.theme {
+        Some(theme) => {
+            save_theme(theme);
+            theme
+        }
+        None => preferences().theme,
+    };
+    let options = tui::Options {
+        animations: !cli.no_animations,
+        theme,
+    };
+    let result = match cli.command.unwrap_or_else(|| CliCommand::Play(GameArgs::default())) {
+        CliCommand::Play(args) => play(&args, options),
+        CliCommand::ServeSlack => {
+            slack();
+            Ok(())
+        }
+        CliCommand::Solve(args) => solve(&args, theme),
+        CliCommand::Daily(args) => {
+            daily_game(&args).and_then(|(board, greeting)| terminal(board, greeting, DAILY_SAVE_PATH, options))
+        }
+        CliCommand::Suggest { count, daily } => suggest(count, daily),
+        CliCommand::Stats => stats(theme),
+        CliCommand::Dict { words } => dict(&words),
+    };
+    if let Err(e) = result {
+        eprintln!("error: {}", e);
+        process::exit(1);
+    }
 }
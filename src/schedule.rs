@@ -0,0 +1,114 @@
+use std::time::Duration;
+
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+
+/// How often the bot checks whether anything is due.
+pub const POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+/// When the daily puzzle is announced and when its results are posted, in the server's local time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Schedule {
+    pub announce_at: NaiveTime,
+    pub digest_at: NaiveTime,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Task {
+    Announce(NaiveDate),
+    Digest(NaiveDate),
+}
+
+/// Works out what's due each time it's polled. Anything that came due before the first poll is
+/// skipped, so restarting the bot doesn't post the same thing twice.
+pub struct Scheduler {
+    schedule: Schedule,
+    last_poll: Option<NaiveDateTime>,
+}
+
+impl Scheduler {
+    pub fn new(schedule: Schedule) -> Self {
+        Scheduler {
+            schedule,
+            last_poll: None,
+        }
+    }
+
+    /// The tasks that came due since the last poll, oldest first.
+    pub fn due(&mut self, now: NaiveDateTime) -> Vec<Task> {
+        let since = match self.last_poll.replace(now) {
+            Some(since) => since,
+            None => return Vec::new(),
+        };
+        let mut tasks = Vec::new();
+        let mut date = since.date();
+        while date <= now.date() {
+            let mut today = [
+                (date.and_time(self.schedule.announce_at), Task::Announce(date)),
+                (date.and_time(self.schedule.digest_at), Task::Digest(date)),
+            ];
+            today.sort_by_key(|(at, _)| *at);
+            tasks.extend(
+                today
+                    .iter()
+                    .filter(|(at, _)| since < *at && *at <= now)
+                    .map(|(_, task)| *task),
+            );
+            date = match date.succ_opt() {
+                Some(date) => date,
+                None => break,
+            };
+        }
+        tasks
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+
+    use crate::schedule::{Schedule, Scheduler, Task};
+
+    fn scheduler() -> Scheduler {
+        Scheduler::new(Schedule {
+            announce_at: NaiveTime::from_hms_opt(9, 0, 0).unwrap(),
+            digest_at: NaiveTime::from_hms_opt(17, 30, 0).unwrap(),
+        })
+    }
+
+    fn at(day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2022, 8, day)
+            .unwrap()
+            .and_hms_opt(hour, minute, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn runs_tasks_once_when_due() {
+        let mut scheduler = scheduler();
+        let day = NaiveDate::from_ymd_opt(2022, 8, 5).unwrap();
+        assert_eq!(scheduler.due(at(5, 8, 59)), vec![]);
+        assert_eq!(scheduler.due(at(5, 9, 0)), vec![Task::Announce(day)]);
+        assert_eq!(scheduler.due(at(5, 9, 1)), vec![]);
+        assert_eq!(scheduler.due(at(5, 17, 45)), vec![Task::Digest(day)]);
+        assert_eq!(scheduler.due(at(5, 23, 0)), vec![]);
+    }
+
+    #[test]
+    fn skips_what_came_due_before_starting() {
+        let mut scheduler = scheduler();
+        assert_eq!(scheduler.due(at(5, 12, 0)), vec![]);
+        assert_eq!(scheduler.due(at(5, 12, 1)), vec![]);
+    }
+
+    #[test]
+    fn catches_up_after_a_gap() {
+        let mut scheduler = scheduler();
+        scheduler.due(at(5, 12, 0));
+        let tasks = scheduler.due(at(6, 10, 0));
+        let (today, tomorrow) = (
+            NaiveDate::from_ymd_opt(2022, 8, 5).unwrap(),
+            NaiveDate::from_ymd_opt(2022, 8, 6).unwrap(),
+        );
+        assert_eq!(tasks, vec![Task::Digest(today), Task::Announce(tomorrow)]);
+    }
+}
@@ -0,0 +1,266 @@
+use std::collections::HashMap;
+use std::io::{self, BufRead, BufReader, Error, ErrorKind, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::thread;
+use std::time::Duration;
+
+use crate::events::Response;
+use crate::signature;
+
+/// Slack's requests are a few kilobytes, so anything much bigger isn't from Slack.
+const MAX_BODY: usize = 1 << 20;
+/// How long a client gets to send its request before the connection is dropped.
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// An HTTP request, as the endpoints see it.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    pub headers: HashMap<String, String>,
+    pub body: Option<String>,
+}
+
+type Handler = Box<dyn Fn(Request) -> Response + Send + Sync>;
+
+/// A small HTTP/1.1 server for the Slack endpoints. Handlers get the request headers, which checking
+/// Slack's signature needs, and the status they answer with is sent back, so a rejected request gets
+/// its 401 rather than a 200.
+#[derive(Default)]
+pub struct HttpServer {
+    routes: Vec<(&'static str, String, Handler)>,
+}
+
+impl HttpServer {
+    pub fn new() -> Self {
+        HttpServer::default()
+    }
+
+    pub fn get<F>(&mut self, path: &str, handler: F)
+    where
+        F: Fn(Request) -> Response + Send + Sync + 'static,
+    {
+        self.routes.push(("GET", path.to_string(), Box::new(handler)));
+    }
+
+    pub fn post<F>(&mut self, path: &str, handler: F)
+    where
+        F: Fn(Request) -> Response + Send + Sync + 'static,
+    {
+        self.routes.push(("POST", path.to_string(), Box::new(handler)));
+    }
+
+    /// Answers `request` with the handler for its method and path, ignoring any query string.
+    pub fn respond(&self, request: Request) -> Response {
+        let path = request.path.split('?').next().unwrap_or_default();
+        match self
+            .routes
+            .iter()
+            .find(|(method, route, _)| *method == request.method && route == path)
+        {
+            Some((_, _, handler)) => handler(request),
+            None => Response::not_found(format!("no {} {}", request.method, path)),
+        }
+    }
+
+    /// Serves requests on `address` until the process exits, each connection on its own thread.
+    /// Fails straight away if the address can't be bound.
+    pub fn listen(&self, address: SocketAddr) -> io::Result<()> {
+        let listener = TcpListener::bind(address)?;
+        thread::scope(|scope| {
+            for stream in listener.incoming() {
+                match stream {
+                    Ok(stream) => {
+                        scope.spawn(move || {
+                            if let Err(e) = self.handle(stream) {
+                                eprintln!("Couldn't answer a request: {}", e);
+                            }
+                        });
+                    }
+                    Err(e) => eprintln!("Couldn't accept a connection: {}", e),
+                }
+            }
+        });
+        Ok(())
+    }
+
+    fn handle(&self, stream: TcpStream) -> io::Result<()> {
+        stream.set_read_timeout(Some(READ_TIMEOUT))?;
+        let reader = BufReader::new(stream.try_clone()?);
+        self.answer(reader, stream)
+    }
+
+    /// Reads one request from `reader` and writes its response to `writer`. The connection is
+    /// closed after every response.
+    fn answer<R: BufRead, W: Write>(&self, reader: R, mut writer: W) -> io::Result<()> {
+        let (route, response) = match read_request(reader) {
+            Ok(request) => (format!("{} {}", request.method, request.path), self.respond(request)),
+            Err(e) => ("request".to_string(), Response::bad_request(e.to_string())),
+        };
+        if response.status != 200 {
+            eprintln!("Rejected {} ({}): {}", route, response.status, response.body);
+        }
+        write_response(&mut writer, &response)
+    }
+}
+
+fn read_request<R: BufRead>(mut reader: R) -> io::Result<Request> {
+    let invalid = |message: &str| Error::new(ErrorKind::InvalidData, message.to_string());
+
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    let mut request_line = line.split_whitespace();
+    let (method, path) = match (request_line.next(), request_line.next()) {
+        (Some(method), Some(path)) => (method.to_string(), path.to_string()),
+        _ => return Err(invalid("malformed request line")),
+    };
+
+    let mut headers = HashMap::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            return Err(Error::new(ErrorKind::UnexpectedEof, "connection closed in the headers"));
+        }
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        let (name, value) = header.split_once(':').ok_or_else(|| invalid("malformed header"))?;
+        headers.insert(name.trim().to_string(), value.trim().to_string());
+    }
+
+    let length = match signature::header(&headers, "Content-Length") {
+        Some(length) => length.parse().map_err(|_| invalid("malformed Content-Length"))?,
+        None => 0,
+    };
+    if length > MAX_BODY {
+        return Err(invalid("body is too large"));
+    }
+    let body = if length == 0 {
+        None
+    } else {
+        let mut body = vec![0; length];
+        reader.read_exact(&mut body)?;
+        Some(String::from_utf8(body).map_err(|_| invalid("body isn't UTF-8"))?)
+    };
+
+    Ok(Request {
+        method,
+        path,
+        headers,
+        body,
+    })
+}
+
+fn write_response<W: Write>(out: &mut W, response: &Response) -> io::Result<()> {
+    let reason = match response.status {
+        200 => "OK",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        404 => "Not Found",
+        _ => "",
+    };
+    write!(
+        out,
+        "HTTP/1.1 {} {}\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        response.status,
+        reason,
+        response.body.len(),
+        response.body
+    )?;
+    out.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::ErrorKind;
+    use std::net::TcpListener;
+
+    use crate::events::{authenticate, Response};
+    use crate::server::{read_request, HttpServer, Request};
+    use crate::signature::{sign, SIGNATURE_HEADER, TIMESTAMP_HEADER};
+
+    const SECRET: &str = "8f742231b10e8888abcd99yyyzzz85a5";
+    const NOW: i64 = 1531420618;
+
+    /// A server with an endpoint that, like the bot's, only answers requests signed with `SECRET`.
+    fn server() -> HttpServer {
+        let mut server = HttpServer::new();
+        server.get("/", |_| Response::ok("health_check"));
+        server.post("/events", |req: Request| {
+            match authenticate(SECRET, &req.headers, req.body.as_deref(), NOW) {
+                Ok(body) => Response::ok(body),
+                Err(response) => response,
+            }
+        });
+        server
+    }
+
+    fn exchange(server: &HttpServer, request: &str) -> String {
+        let mut out = Vec::new();
+        server.answer(request.as_bytes(), &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    fn post(body: &str, signature: &str) -> String {
+        format!(
+            "POST /events HTTP/1.1\r\nHost: localhost\r\n{}: {}\r\n{}: {}\r\nContent-Length: {}\r\n\r\n{}",
+            TIMESTAMP_HEADER,
+            NOW,
+            SIGNATURE_HEADER.to_lowercase(),
+            signature,
+            body.len(),
+            body
+        )
+    }
+
+    #[test]
+    fn reads_requests() {
+        let raw = "POST /wordle?x=1 HTTP/1.1\r\nContent-Type: text/plain\r\ncontent-length: 5\r\n\r\nhello";
+        let request = read_request(raw.as_bytes()).unwrap();
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.path, "/wordle?x=1");
+        assert_eq!(request.headers["Content-Type"], "text/plain");
+        assert_eq!(request.body.as_deref(), Some("hello"));
+
+        let request = read_request("GET / HTTP/1.1\r\n\r\n".as_bytes()).unwrap();
+        assert_eq!(request.body, None);
+        assert!(read_request("GET / HTTP/1.1\r\nHost".as_bytes()).is_err());
+        assert!(read_request("POST / HTTP/1.1\r\nContent-Length: 10\r\n\r\nshort".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn sends_the_handlers_status() {
+        let server = server();
+        let body = "{\"type\":\"event_callback\"}";
+        let signed = exchange(&server, &post(body, &sign(SECRET, &NOW.to_string(), body)));
+        assert!(signed.starts_with("HTTP/1.1 200 OK\r\n"), "{}", signed);
+        assert!(signed.ends_with(&format!(
+            "Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+            body.len(),
+            body
+        )));
+
+        let forged = exchange(&server, &post(body, &sign("not the secret", &NOW.to_string(), body)));
+        assert!(forged.starts_with("HTTP/1.1 401 Unauthorized\r\n"), "{}", forged);
+        assert!(forged.ends_with("signature doesn't match"));
+
+        let unsigned = exchange(&server, "POST /events HTTP/1.1\r\nContent-Length: 2\r\n\r\n{}");
+        assert!(unsigned.starts_with("HTTP/1.1 401 "), "{}", unsigned);
+    }
+
+    #[test]
+    fn reports_addresses_it_cant_listen_on() {
+        let taken = TcpListener::bind("127.0.0.1:0").unwrap();
+        let e = server().listen(taken.local_addr().unwrap()).unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::AddrInUse);
+    }
+
+    #[test]
+    fn routes_by_method_and_path() {
+        let server = server();
+        assert!(exchange(&server, "GET /?probe=1 HTTP/1.1\r\n\r\n").ends_with("\r\n\r\nhealth_check"));
+        assert!(exchange(&server, "GET /events HTTP/1.1\r\n\r\n").starts_with("HTTP/1.1 404 Not Found\r\n"));
+        assert!(exchange(&server, "nonsense\r\n\r\n").starts_with("HTTP/1.1 400 Bad Request\r\n"));
+    }
+}
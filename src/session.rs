@@ -0,0 +1,603 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::board::{Board, BoardConfig, SavedGame};
+use crate::daily;
+use crate::dictionary::SharedDictionary;
+use crate::stats::{GameRecord, Stats};
+use crate::theme::Theme;
+
+/// Who a Slack game belongs to. `user` is only set when everyone plays their own game.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct SessionKey {
+    pub team: String,
+    pub channel: String,
+    pub user: Option<String>,
+}
+
+/// Whether a channel plays one game together or everyone in it gets their own.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionMode {
+    Channel,
+    User,
+}
+
+impl FromStr for SessionMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "channel" => Ok(SessionMode::Channel),
+            "user" => Ok(SessionMode::User),
+            other => Err(format!(
+                "unknown session mode {:?}, expected \"channel\" or \"user\"",
+                other
+            )),
+        }
+    }
+}
+
+impl fmt::Display for SessionMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SessionMode::Channel => write!(f, "channel"),
+            SessionMode::User => write!(f, "user"),
+        }
+    }
+}
+
+pub struct Session {
+    pub board: Board,
+    pub last_active: DateTime<Utc>,
+    /// The `ts` of the message the game's thread hangs off, once it's been posted.
+    pub thread_ts: Option<String>,
+}
+
+/// How many games a session has finished, for `/wordle stats`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Tally {
+    pub played: u32,
+    pub won: u32,
+}
+
+impl Tally {
+    pub fn record(&mut self, won: bool) {
+        self.played += 1;
+        if won {
+            self.won += 1;
+        }
+    }
+}
+
+/// What a player has chosen for themselves, which follows them from game to game. The terminal keeps
+/// one of these too.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Preferences {
+    #[serde(default)]
+    pub theme: Theme,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SavedSession {
+    key: SessionKey,
+    game: SavedGame,
+    last_active: DateTime<Utc>,
+    #[serde(default)]
+    thread_ts: Option<String>,
+}
+
+/// Everything `save` writes. Every field has a default, so files from before one was added still load.
+#[derive(Default, Serialize, Deserialize)]
+struct SavedSessions {
+    #[serde(default)]
+    sessions: Vec<SavedSession>,
+    #[serde(default)]
+    tallies: Vec<(SessionKey, Tally)>,
+    #[serde(default)]
+    daily: Vec<(String, SavedGame)>,
+    #[serde(default)]
+    stats: Vec<(String, Stats)>,
+    #[serde(default)]
+    preferences: Vec<(String, Preferences)>,
+}
+
+/// A save file in either shape: the first ones were just the list of games.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SaveFile {
+    Sessions(Vec<SavedSession>),
+    Saved(SavedSessions),
+}
+
+/// Every game the Slack bot is running. Games are created on first use, replaced with a fresh one
+/// once they're finished, and dropped after sitting idle for `idle_timeout`.
+pub struct SessionManager {
+    mode: SessionMode,
+    config: BoardConfig,
+    idle_timeout: Duration,
+    dictionary: SharedDictionary,
+    sessions: HashMap<SessionKey, Session>,
+    /// Kept apart from the games so they outlive them.
+    tallies: HashMap<SessionKey, Tally>,
+    /// Everyone's go at the daily puzzle, by user. These don't expire, they're replaced the next day.
+    daily: HashMap<String, Board>,
+    /// Everyone's finished games of their own, by user: daily puzzles, and games in user mode.
+    stats: HashMap<String, Stats>,
+    /// By user, whatever channel they're playing in.
+    preferences: HashMap<String, Preferences>,
+}
+
+impl SessionManager {
+    pub fn new(mode: SessionMode, config: BoardConfig, idle_timeout: Duration, dictionary: SharedDictionary) -> Self {
+        SessionManager {
+            mode,
+            config,
+            idle_timeout,
+            dictionary,
+            sessions: HashMap::new(),
+            tallies: HashMap::new(),
+            daily: HashMap::new(),
+            stats: HashMap::new(),
+            preferences: HashMap::new(),
+        }
+    }
+
+    pub fn mode(&self) -> SessionMode {
+        self.mode
+    }
+
+    /// The key for a message from `user` in `channel`, depending on the session mode.
+    pub fn key(&self, team: &str, channel: &str, user: &str) -> SessionKey {
+        SessionKey {
+            team: team.to_string(),
+            channel: channel.to_string(),
+            user: match self.mode {
+                SessionMode::Channel => None,
+                SessionMode::User => Some(user.to_string()),
+            },
+        }
+    }
+
+    #[cfg(test)]
+    pub fn get(&self, key: &SessionKey) -> Option<&Board> {
+        self.sessions.get(key).map(|s| &s.board)
+    }
+
+    /// The game for `key`, starting a new one if there isn't one or the last one is over. A new
+    /// game has the same word length as the last one. Returns `None` only if the dictionary has no
+    /// answers for that length.
+    pub fn game(&mut self, key: &SessionKey, now: DateTime<Utc>) -> Option<&mut Board> {
+        match self.sessions.get(key) {
+            Some(session) if session.board.is_over() => {
+                let config = session.board.config();
+                return self.start(key, config, now);
+            }
+            None => return self.start(key, self.config, now),
+            Some(_) => {}
+        }
+        let session = self.sessions.get_mut(key)?;
+        session.last_active = now;
+        Some(&mut session.board)
+    }
+
+    /// Throws away whatever game `key` had and starts a new one with `config`. Returns `None`, and
+    /// leaves the old game alone, if the dictionary has no answers of that length.
+    pub fn start(&mut self, key: &SessionKey, config: BoardConfig, now: DateTime<Utc>) -> Option<&mut Board> {
+        let dictionary = Box::new(Arc::clone(&self.dictionary));
+        let board = Board::random(config, dictionary, &mut rand::thread_rng())?;
+        let session = Session {
+            board,
+            last_active: now,
+            thread_ts: None,
+        };
+        self.sessions.insert(key.clone(), session);
+        self.sessions.get_mut(key).map(|s| &mut s.board)
+    }
+
+    /// The thread `key`'s game is being played in, if its first message has been posted.
+    pub fn thread(&self, key: &SessionKey) -> Option<&str> {
+        self.sessions.get(key)?.thread_ts.as_deref()
+    }
+
+    pub fn set_thread(&mut self, key: &SessionKey, ts: String) {
+        if let Some(session) = self.sessions.get_mut(key) {
+            session.thread_ts = Some(ts);
+        }
+    }
+
+    /// The settings new games start with.
+    pub fn config(&self) -> BoardConfig {
+        self.config
+    }
+
+    /// Counts a finished game towards `key`'s tally.
+    pub fn record(&mut self, key: &SessionKey, won: bool) {
+        self.tallies.entry(key.clone()).or_default().record(won);
+    }
+
+    pub fn tally(&self, key: &SessionKey) -> Tally {
+        self.tallies.get(key).copied().unwrap_or_default()
+    }
+
+    /// `user`'s game of the puzzle for `date`, starting it if they haven't yet. Returns `None` for
+    /// dates before the first puzzle.
+    pub fn daily_game(&mut self, user: &str, date: NaiveDate) -> Option<&mut Board> {
+        let puzzle = daily::puzzle_number(date)?;
+        let current = self.daily.get(user).and_then(Board::puzzle_number) == Some(puzzle);
+        if !current {
+            let board = Board::daily(date, self.config, Box::new(Arc::clone(&self.dictionary)))?;
+            self.daily.insert(user.to_string(), board);
+        }
+        self.daily.get_mut(user)
+    }
+
+    /// Everyone who has played `puzzle`, by user id.
+    pub fn daily_games(&self, puzzle: u32) -> Vec<(&str, &Board)> {
+        let mut games: Vec<(&str, &Board)> = self
+            .daily
+            .iter()
+            .filter(|(_, board)| board.puzzle_number() == Some(puzzle))
+            .map(|(user, board)| (user.as_str(), board))
+            .collect();
+        games.sort_by_key(|(user, _)| *user);
+        games
+    }
+
+    /// Adds a game `user` finished to their stats.
+    pub fn record_game(&mut self, user: &str, game: GameRecord) {
+        self.stats.entry(user.to_string()).or_default().record(game);
+    }
+
+    pub fn stats(&self, user: &str) -> Stats {
+        self.stats.get(user).cloned().unwrap_or_default()
+    }
+
+    pub fn preferences(&self, user: &str) -> Preferences {
+        self.preferences.get(user).copied().unwrap_or_default()
+    }
+
+    pub fn set_theme(&mut self, user: &str, theme: Theme) {
+        self.preferences.entry(user.to_string()).or_default().theme = theme;
+    }
+
+    /// Drops every game nobody has touched for longer than the idle timeout, returning how many.
+    pub fn expire(&mut self, now: DateTime<Utc>) -> usize {
+        let before = self.sessions.len();
+        let idle_timeout = self.idle_timeout;
+        self.sessions.retain(|_, s| now - s.last_active <= idle_timeout);
+        before - self.sessions.len()
+    }
+
+    #[cfg(test)]
+    pub fn len(&self) -> usize {
+        self.sessions.len()
+    }
+
+    /// Writes every game to `path` as JSON, creating its directory if needed.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let path = path.as_ref();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let saved = SavedSessions {
+            sessions: self
+                .sessions
+                .iter()
+                .map(|(key, s)| SavedSession {
+                    key: key.clone(),
+                    game: s.board.to_saved(),
+                    last_active: s.last_active,
+                    thread_ts: s.thread_ts.clone(),
+                })
+                .collect(),
+            tallies: self.tallies.iter().map(|(key, t)| (key.clone(), *t)).collect(),
+            daily: self
+                .daily
+                .iter()
+                .map(|(user, b)| (user.clone(), b.to_saved()))
+                .collect(),
+            stats: self.stats.iter().map(|(user, s)| (user.clone(), s.clone())).collect(),
+            preferences: self.preferences.iter().map(|(user, p)| (user.clone(), *p)).collect(),
+        };
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, serde_json::to_string_pretty(&saved)?)?;
+        fs::rename(tmp, path)
+    }
+
+    /// Reads the games written by `save` back in, keeping this manager's settings. A game that
+    /// can't be resumed is logged and left out rather than losing everyone else's.
+    pub fn load<P: AsRef<Path>>(&mut self, path: P) -> io::Result<()> {
+        let saved = match serde_json::from_str(&fs::read_to_string(path)?)? {
+            SaveFile::Sessions(sessions) => SavedSessions {
+                sessions,
+                ..SavedSessions::default()
+            },
+            SaveFile::Saved(saved) => saved,
+        };
+        for s in saved.sessions {
+            let board = match Board::from_saved(s.game, Box::new(Arc::clone(&self.dictionary))) {
+                Ok(board) => board,
+                Err(e) => {
+                    eprintln!("Couldn't resume the game in {}: {}", s.key.channel, e);
+                    continue;
+                }
+            };
+            let session = Session {
+                board,
+                last_active: s.last_active,
+                thread_ts: s.thread_ts,
+            };
+            self.sessions.insert(s.key, session);
+        }
+        self.tallies.extend(saved.tallies);
+        for (user, game) in saved.daily {
+            match Board::from_saved(game, Box::new(Arc::clone(&self.dictionary))) {
+                Ok(board) => {
+                    self.daily.insert(user, board);
+                }
+                Err(e) => eprintln!("Couldn't resume {}'s daily puzzle: {}", user, e),
+            }
+        }
+        self.stats.extend(saved.stats);
+        self.preferences.extend(saved.preferences);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use chrono::{Duration, NaiveDate, TimeZone, Utc};
+
+    use crate::board::BoardConfig;
+    use crate::dictionary::WebDictionary;
+    use crate::session::{SessionManager, SessionMode, Tally};
+    use crate::stats::{GameRecord, Mode, Stats};
+    use crate::theme::Theme;
+
+    fn daily(puzzle: u32, won: bool) -> GameRecord {
+        GameRecord {
+            date: NaiveDate::from_ymd_opt(2022, 8, 5).unwrap(),
+            answer: "rusty".to_string(),
+            guesses: if won { 3 } else { 6 },
+            max_guesses: 6,
+            won,
+            mode: Mode::Daily(puzzle),
+            hard_mode: false,
+            hints: 0,
+        }
+    }
+
+    fn manager(mode: SessionMode) -> SessionManager {
+        let dictionary = Arc::new(WebDictionary::new());
+        SessionManager::new(mode, BoardConfig::default(), Duration::hours(1), dictionary)
+    }
+
+    #[test]
+    fn channel_mode_shares_a_game() {
+        let manager = manager(SessionMode::Channel);
+        assert_eq!(manager.key("T1", "C1", "U1"), manager.key("T1", "C1", "U2"));
+        assert_ne!(manager.key("T1", "C1", "U1"), manager.key("T1", "C2", "U1"));
+        assert_ne!(manager.key("T1", "C1", "U1"), manager.key("T2", "C1", "U1"));
+    }
+
+    #[test]
+    fn user_mode_separates_games() {
+        let manager = manager(SessionMode::User);
+        assert_ne!(manager.key("T1", "C1", "U1"), manager.key("T1", "C1", "U2"));
+        assert_eq!(manager.key("T1", "C1", "U1").user, Some("U1".to_string()));
+    }
+
+    #[test]
+    fn creates_and_reuses_games() {
+        let mut manager = manager(SessionMode::User);
+        let now = Utc.with_ymd_and_hms(2022, 8, 5, 12, 0, 0).unwrap();
+        let key = manager.key("T1", "C1", "U1");
+        assert!(manager.get(&key).is_none());
+        manager.game(&key, now).unwrap().guess("aahed").unwrap();
+        assert_eq!(manager.game(&key, now).unwrap().guesses_used(), 1);
+        assert_eq!(manager.get(&key).unwrap().guesses_used(), 1);
+        assert_eq!(manager.len(), 1);
+    }
+
+    #[test]
+    fn new_game_after_the_last_one_ends() {
+        let mut manager = manager(SessionMode::Channel);
+        let now = Utc.with_ymd_and_hms(2022, 8, 5, 12, 0, 0).unwrap();
+        let key = manager.key("T1", "C1", "U1");
+        let board = manager.game(&key, now).unwrap();
+        while !board.is_over() {
+            board.guess("crane").unwrap();
+        }
+        manager.set_thread(&key, "1.000000".to_string());
+        assert!(manager.get(&key).unwrap().is_over());
+        assert_eq!(manager.game(&key, now).unwrap().guesses_used(), 0);
+        assert_eq!(manager.thread(&key), None);
+    }
+
+    #[test]
+    fn start_changes_word_length() {
+        let mut manager = manager(SessionMode::Channel);
+        let now = Utc.with_ymd_and_hms(2022, 8, 5, 12, 0, 0).unwrap();
+        let key = manager.key("T1", "C1", "U1");
+        manager.game(&key, now).unwrap().guess("aahed").unwrap();
+        let six = BoardConfig {
+            word_length: 6,
+            ..manager.config()
+        };
+        let board = manager.start(&key, six, now).unwrap();
+        assert_eq!(board.guesses_used(), 0);
+        board.give_up();
+        // the next game sticks with six letters
+        assert_eq!(manager.game(&key, now).unwrap().config().word_length, 6);
+
+        let none = BoardConfig {
+            word_length: 12,
+            ..manager.config()
+        };
+        assert!(manager.start(&key, none, now).is_none());
+        assert_eq!(manager.get(&key).unwrap().config().word_length, 6);
+    }
+
+    #[test]
+    fn tallies_outlive_games() {
+        let mut manager = manager(SessionMode::Channel);
+        let now = Utc.with_ymd_and_hms(2022, 8, 5, 12, 0, 0).unwrap();
+        let key = manager.key("T1", "C1", "U1");
+        manager.game(&key, now);
+        manager.record(&key, true);
+        manager.expire(now + Duration::days(2));
+        assert!(manager.get(&key).is_none());
+        assert_eq!(manager.tally(&key), Tally { played: 1, won: 1 });
+    }
+
+    #[test]
+    fn daily_games_per_player() {
+        let mut manager = manager(SessionMode::Channel);
+        let today = NaiveDate::from_ymd_opt(2022, 8, 5).unwrap();
+        manager.daily_game("U1", today).unwrap().guess("aahed").unwrap();
+        manager.daily_game("U2", today).unwrap();
+        assert_eq!(manager.daily_game("U1", today).unwrap().guesses_used(), 1);
+        assert_eq!(manager.daily_game("U2", today).unwrap().puzzle_number(), Some(412));
+        let players: Vec<&str> = manager.daily_games(412).iter().map(|(user, _)| *user).collect();
+        assert_eq!(players, vec!["U1", "U2"]);
+
+        let tomorrow = today.succ_opt().unwrap();
+        assert_eq!(manager.daily_game("U1", tomorrow).unwrap().guesses_used(), 0);
+        assert_eq!(manager.daily_games(412).len(), 1);
+        assert!(manager
+            .daily_game("U1", NaiveDate::from_ymd_opt(2021, 1, 1).unwrap())
+            .is_none());
+    }
+
+    #[test]
+    fn stats_per_player() {
+        let mut manager = manager(SessionMode::Channel);
+        manager.record_game("U1", daily(410, true));
+        manager.record_game("U1", daily(411, true));
+        manager.record_game("U1", daily(412, true));
+        assert_eq!(manager.stats("U1").daily_streak(413), 3);
+        assert_eq!(manager.stats("U1").daily_streak(414), 0);
+        manager.record_game("U1", daily(414, true));
+        manager.record_game("U1", daily(415, false));
+        let stats = manager.stats("U1");
+        assert_eq!(stats.daily_streak(415), 0);
+        assert_eq!((stats.summary().played, stats.summary().max_streak), (5, 4));
+        assert_eq!(manager.stats("U2"), Stats::default());
+    }
+
+    #[test]
+    fn expires_idle_games() {
+        let mut manager = manager(SessionMode::User);
+        let start = Utc.with_ymd_and_hms(2022, 8, 5, 12, 0, 0).unwrap();
+        let old = manager.key("T1", "C1", "U1");
+        let recent = manager.key("T1", "C1", "U2");
+        manager.game(&old, start);
+        manager.game(&recent, start + Duration::minutes(45));
+        assert_eq!(manager.expire(start + Duration::minutes(90)), 1);
+        assert!(manager.get(&old).is_none());
+        assert!(manager.get(&recent).is_some());
+    }
+
+    #[test]
+    fn save_and_load() {
+        let path = std::env::temp_dir().join(format!("wordle-sessions-{}.json", std::process::id()));
+        let now = Utc.with_ymd_and_hms(2022, 8, 5, 12, 0, 0).unwrap();
+        let mut manager = manager(SessionMode::User);
+        let key = manager.key("T1", "C1", "U1");
+        manager.game(&key, now).unwrap().guess("aahed").unwrap();
+        manager.record(&key, true);
+        manager.record(&key, false);
+        manager.set_thread(&key, "1.000000".to_string());
+        manager
+            .daily_game("U1", now.date_naive())
+            .unwrap()
+            .guess("aahed")
+            .unwrap();
+        manager.record_game("U1", daily(411, true));
+        manager.set_theme("U1", Theme::HighContrast);
+        manager.save(&path).unwrap();
+
+        let mut loaded = self::manager(SessionMode::User);
+        loaded.load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.get(&key).unwrap().guesses(), vec!["aahed"]);
+        assert_eq!(loaded.expire(now + Duration::minutes(30)), 0);
+        assert_eq!(loaded.tally(&key), Tally { played: 2, won: 1 });
+        assert_eq!(loaded.thread(&key), Some("1.000000"));
+        assert_eq!(loaded.daily_games(412)[0].1.guesses(), vec!["aahed"]);
+        assert_eq!(loaded.stats("U1").daily_streak(412), 1);
+        assert_eq!(loaded.preferences("U1").theme, Theme::HighContrast);
+        assert_eq!(loaded.preferences("U2").theme, Theme::Standard);
+    }
+
+    #[test]
+    fn loads_older_saves() {
+        let path = std::env::temp_dir().join(format!("wordle-old-sessions-{}.json", std::process::id()));
+        let now = Utc.with_ymd_and_hms(2022, 8, 5, 12, 0, 0).unwrap();
+        let mut manager = manager(SessionMode::User);
+        let key = manager.key("T1", "C1", "U1");
+        manager.game(&key, now).unwrap().guess("aahed").unwrap();
+        manager.save(&path).unwrap();
+
+        // the first saves were only the list of games, without thread timestamps
+        let mut saved: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        saved["sessions"][0].as_object_mut().unwrap().remove("thread_ts");
+        std::fs::write(&path, saved["sessions"].to_string()).unwrap();
+        let mut loaded = self::manager(SessionMode::User);
+        loaded.load(&path).unwrap();
+        assert_eq!(loaded.get(&key).unwrap().guesses(), vec!["aahed"]);
+        assert_eq!(loaded.thread(&key), None);
+
+        std::fs::write(&path, "{\"preferences\": [[\"U1\", {\"theme\": \"dark\"}]]}").unwrap();
+        let mut loaded = self::manager(SessionMode::User);
+        loaded.load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.len(), 0);
+        assert_eq!(loaded.preferences("U1").theme, Theme::Dark);
+    }
+
+    #[test]
+    fn skips_games_it_cant_resume() {
+        let path = std::env::temp_dir().join(format!("wordle-bad-sessions-{}.json", std::process::id()));
+        let now = Utc.with_ymd_and_hms(2022, 8, 5, 12, 0, 0).unwrap();
+        let mut manager = manager(SessionMode::User);
+        let good = manager.key("T1", "C1", "U1");
+        let bad = manager.key("T1", "C1", "U2");
+        manager.game(&good, now).unwrap().guess("aahed").unwrap();
+        manager.game(&bad, now).unwrap().guess("aahed").unwrap();
+        manager.daily_game("U1", now.date_naive()).unwrap();
+        manager.daily_game("U2", now.date_naive()).unwrap();
+        manager.set_theme("U1", Theme::Dark);
+        manager.save(&path).unwrap();
+
+        let mut saved: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        for session in saved["sessions"].as_array_mut().unwrap() {
+            if session["key"]["user"] == "U2" {
+                session["game"]["guesses"] = serde_json::json!(["toolong"]);
+            }
+        }
+        for daily in saved["daily"].as_array_mut().unwrap() {
+            if daily[0] == "U2" {
+                daily[1]["state"] = serde_json::json!("Won");
+            }
+        }
+        std::fs::write(&path, saved.to_string()).unwrap();
+        let mut loaded = self::manager(SessionMode::User);
+        loaded.load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.get(&good).unwrap().guesses(), vec!["aahed"]);
+        assert!(loaded.get(&bad).is_none());
+        assert_eq!(loaded.daily_games(412).len(), 1);
+        assert_eq!(loaded.preferences("U1").theme, Theme::Dark);
+    }
+}
@@ -0,0 +1,223 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+/// How far a request's timestamp may be from our clock, in seconds, before it's treated as a replay.
+pub const REPLAY_WINDOW: i64 = 60 * 5;
+
+pub const SIGNATURE_HEADER: &str = "X-Slack-Signature";
+pub const TIMESTAMP_HEADER: &str = "X-Slack-Request-Timestamp";
+
+const VERSION: &str = "v0";
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum SignatureError {
+    MissingHeader(&'static str),
+    BadTimestamp(String),
+    Expired { timestamp: i64, now: i64 },
+    BadSignature,
+}
+
+impl fmt::Display for SignatureError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SignatureError::MissingHeader(name) => write!(f, "missing {} header", name),
+            SignatureError::BadTimestamp(timestamp) => write!(f, "{:?} is not a timestamp", timestamp),
+            SignatureError::Expired { timestamp, now } => {
+                write!(f, "request is {} seconds old", now - timestamp)
+            }
+            SignatureError::BadSignature => write!(f, "signature doesn't match"),
+        }
+    }
+}
+
+impl std::error::Error for SignatureError {}
+
+/// Header names are case-insensitive, and proxies don't agree on how to capitalise them.
+pub fn header<'a>(headers: &'a HashMap<String, String>, name: &str) -> Option<&'a str> {
+    headers
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.trim())
+}
+
+/// The `X-Slack-Signature` Slack would send for `body` at `timestamp`.
+#[cfg(test)]
+pub fn sign(secret: &str, timestamp: &str, body: &str) -> String {
+    format!(
+        "{}={}",
+        VERSION,
+        hex::encode(mac(secret, timestamp, body).finalize().into_bytes())
+    )
+}
+
+/// Checks that a request was signed with our signing secret within the replay window of `now`
+/// (seconds since the epoch). See https://api.slack.com/authentication/verifying-requests-from-slack
+pub fn verify(secret: &str, headers: &HashMap<String, String>, body: &str, now: i64) -> Result<(), SignatureError> {
+    let timestamp = header(headers, TIMESTAMP_HEADER).ok_or(SignatureError::MissingHeader(TIMESTAMP_HEADER))?;
+    let signature = header(headers, SIGNATURE_HEADER).ok_or(SignatureError::MissingHeader(SIGNATURE_HEADER))?;
+
+    let seconds: i64 = timestamp
+        .parse()
+        .map_err(|_| SignatureError::BadTimestamp(timestamp.to_string()))?;
+    if (now - seconds).abs() > REPLAY_WINDOW {
+        return Err(SignatureError::Expired {
+            timestamp: seconds,
+            now,
+        });
+    }
+
+    let expected = signature
+        .strip_prefix(VERSION)
+        .and_then(|s| s.strip_prefix('='))
+        .and_then(|s| hex::decode(s).ok())
+        .ok_or(SignatureError::BadSignature)?;
+    // verify_slice compares in constant time
+    mac(secret, timestamp, body)
+        .verify_slice(&expected)
+        .map_err(|_| SignatureError::BadSignature)
+}
+
+fn mac(secret: &str, timestamp: &str, body: &str) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC takes keys of any length");
+    mac.update(format!("{}:{}:", VERSION, timestamp).as_bytes());
+    mac.update(body.as_bytes());
+    mac
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::collections::HashMap;
+
+    use serde::Deserialize;
+
+    use crate::signature::{sign, verify, SignatureError, SIGNATURE_HEADER, TIMESTAMP_HEADER};
+
+    /// A request recorded from Slack, with the secret it was signed with.
+    #[derive(Deserialize)]
+    pub(crate) struct Fixture {
+        pub secret: String,
+        pub timestamp: String,
+        pub signature: String,
+        pub body: String,
+    }
+
+    impl Fixture {
+        pub fn load(json: &str) -> Self {
+            serde_json::from_str(json).unwrap()
+        }
+
+        pub fn headers(&self) -> HashMap<String, String> {
+            HashMap::from([
+                (TIMESTAMP_HEADER.to_string(), self.timestamp.clone()),
+                (SIGNATURE_HEADER.to_string(), self.signature.clone()),
+            ])
+        }
+
+        pub fn now(&self) -> i64 {
+            self.timestamp.parse().unwrap()
+        }
+    }
+
+    pub(crate) const SLASH_COMMAND: &str = include_str!("../tests/fixtures/slack/slash_command.json");
+    pub(crate) const MESSAGE_EVENT: &str = include_str!("../tests/fixtures/slack/message_event.json");
+    pub(crate) const URL_VERIFICATION: &str = include_str!("../tests/fixtures/slack/url_verification.json");
+
+    #[test]
+    fn accepts_recorded_requests() {
+        for json in [SLASH_COMMAND, MESSAGE_EVENT, URL_VERIFICATION] {
+            let fixture = Fixture::load(json);
+            assert_eq!(
+                sign(&fixture.secret, &fixture.timestamp, &fixture.body),
+                fixture.signature
+            );
+            assert_eq!(
+                verify(&fixture.secret, &fixture.headers(), &fixture.body, fixture.now()),
+                Ok(())
+            );
+        }
+    }
+
+    #[test]
+    fn header_names_are_case_insensitive() {
+        let fixture = Fixture::load(MESSAGE_EVENT);
+        let headers = fixture
+            .headers()
+            .into_iter()
+            .map(|(name, value)| (name.to_lowercase(), value))
+            .collect();
+        assert_eq!(verify(&fixture.secret, &headers, &fixture.body, fixture.now()), Ok(()));
+    }
+
+    #[test]
+    fn rejects_wrong_secret() {
+        let fixture = Fixture::load(MESSAGE_EVENT);
+        assert_eq!(
+            verify("not the secret", &fixture.headers(), &fixture.body, fixture.now()),
+            Err(SignatureError::BadSignature)
+        );
+    }
+
+    #[test]
+    fn rejects_tampered_body() {
+        let fixture = Fixture::load(MESSAGE_EVENT);
+        let body = fixture.body.replace("crane", "tulip");
+        assert_eq!(
+            verify(&fixture.secret, &fixture.headers(), &body, fixture.now()),
+            Err(SignatureError::BadSignature)
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_signature() {
+        let fixture = Fixture::load(MESSAGE_EVENT);
+        for signature in ["", "v0=", "v0=zz", "v1=abcd", &fixture.signature[3..]] {
+            let mut headers = fixture.headers();
+            headers.insert(SIGNATURE_HEADER.to_string(), signature.to_string());
+            assert_eq!(
+                verify(&fixture.secret, &headers, &fixture.body, fixture.now()),
+                Err(SignatureError::BadSignature)
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_missing_headers() {
+        let fixture = Fixture::load(MESSAGE_EVENT);
+        for name in [TIMESTAMP_HEADER, SIGNATURE_HEADER] {
+            let mut headers = fixture.headers();
+            headers.remove(name);
+            assert_eq!(
+                verify(&fixture.secret, &headers, &fixture.body, fixture.now()),
+                Err(SignatureError::MissingHeader(name))
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_replays() {
+        let fixture = Fixture::load(MESSAGE_EVENT);
+        let now = fixture.now();
+        assert_eq!(
+            verify(&fixture.secret, &fixture.headers(), &fixture.body, now + 300),
+            Ok(())
+        );
+        assert_eq!(
+            verify(&fixture.secret, &fixture.headers(), &fixture.body, now + 301),
+            Err(SignatureError::Expired {
+                timestamp: now,
+                now: now + 301
+            })
+        );
+        assert!(verify(&fixture.secret, &fixture.headers(), &fixture.body, now - 301).is_err());
+
+        let mut headers = fixture.headers();
+        headers.insert(TIMESTAMP_HEADER.to_string(), "yesterday".to_string());
+        assert_eq!(
+            verify(&fixture.secret, &headers, &fixture.body, now),
+            Err(SignatureError::BadTimestamp("yesterday".to_string()))
+        );
+    }
+}
@@ -1,19 +1,518 @@
-use std::collections::HashMap;
-
-use reqwest::header;
-
-pub fn send_slack_message_to_channel(channel: &str, message: &str) {
-    let mut map = HashMap::new();
-    map.insert("channel", channel);
-    map.insert("text", message);
-    let mut headers = header::HeaderMap::new();
-    headers.insert(
-        header::AUTHORIZATION,
-        header::HeaderValue::from_static("Bearer SLACK_TOKEN_HERE"),
-    );
-    let client = reqwest::blocking::Client::builder()
-        .default_headers(headers)
-        .build()
-        .unwrap();
-    let res = client.post("https://slack.com/api/chat.postMessage").json(&map).send();
+use std::fmt;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use reqwest::{blocking, header, StatusCode};
+use serde_json::{json, Value};
+
+#[cfg(test)]
+use std::sync::Mutex;
+
+/// How many times a rate limited call is retried before giving up.
+const MAX_RETRIES: u32 = 3;
+/// How long to wait after a 429 that doesn't say, and the longest we'll wait when it does.
+const DEFAULT_RETRY_AFTER: u64 = 1;
+const MAX_RETRY_AFTER: u64 = 30;
+
+#[derive(Debug)]
+pub enum SlackError {
+    Http(reqwest::Error),
+    /// Slack answered with `"ok": false`, and this was its `error`.
+    Api(String),
+    RateLimited {
+        retry_after: u64,
+    },
+    InvalidResponse(String),
+}
+
+impl fmt::Display for SlackError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SlackError::Http(e) => write!(f, "couldn't reach Slack: {}", e),
+            SlackError::Api(error) => write!(f, "Slack said {}", error),
+            SlackError::RateLimited { retry_after } => {
+                write!(f, "still rate limited, Slack asked to wait {} seconds", retry_after)
+            }
+            SlackError::InvalidResponse(message) => write!(f, "unexpected response from Slack: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for SlackError {}
+
+impl From<reqwest::Error> for SlackError {
+    fn from(e: reqwest::Error) -> Self {
+        SlackError::Http(e)
+    }
+}
+
+/// Where a message ended up, which is what Slack needs to update it or reply in its thread.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PostedMessage {
+    pub channel: String,
+    pub ts: String,
+}
+
+/// What to post. `text` shows up in notifications, and instead of `blocks` in clients that can't
+/// render them.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Message {
+    pub text: String,
+    pub blocks: Option<Value>,
+    /// Posts the message as a reply in this thread.
+    pub thread_ts: Option<String>,
+    /// Shows a thread reply in the channel too.
+    pub reply_broadcast: bool,
+}
+
+impl Message {
+    pub fn new<S: Into<String>>(text: S) -> Self {
+        Message {
+            text: text.into(),
+            ..Message::default()
+        }
+    }
+
+    pub fn with_blocks(mut self, blocks: Value) -> Self {
+        self.blocks = Some(blocks);
+        self
+    }
+
+    #[cfg(test)]
+    pub fn in_thread<S: Into<String>>(mut self, thread_ts: S) -> Self {
+        self.thread_ts = Some(thread_ts.into());
+        self
+    }
+
+    /// The arguments `chat.postMessage` and friends share.
+    fn to_json(&self, channel: &str) -> Value {
+        let mut v = json!({ "channel": channel, "text": self.text });
+        if let Some(blocks) = &self.blocks {
+            v["blocks"] = blocks.clone();
+        }
+        if let Some(thread_ts) = &self.thread_ts {
+            v["thread_ts"] = json!(thread_ts);
+            if self.reply_broadcast {
+                v["reply_broadcast"] = json!(true);
+            }
+        }
+        v
+    }
+}
+
+impl From<&str> for Message {
+    fn from(text: &str) -> Self {
+        Message::new(text)
+    }
+}
+
+/// The parts of the Web API the bot uses.
+pub trait SlackClient: Send + Sync {
+    fn post_message(&self, channel: &str, message: &Message) -> Result<PostedMessage, SlackError>;
+
+    /// A message in `channel` that only `user` can see.
+    fn post_ephemeral(&self, channel: &str, user: &str, message: &Message) -> Result<(), SlackError>;
+
+    /// Replaces the message posted at `ts`. Threading doesn't change, so `message.thread_ts` is ignored.
+    fn update_message(&self, channel: &str, ts: &str, message: &Message) -> Result<(), SlackError>;
+}
+
+impl<T: SlackClient + ?Sized> SlackClient for Arc<T> {
+    fn post_message(&self, channel: &str, message: &Message) -> Result<PostedMessage, SlackError> {
+        (**self).post_message(channel, message)
+    }
+
+    fn post_ephemeral(&self, channel: &str, user: &str, message: &Message) -> Result<(), SlackError> {
+        (**self).post_ephemeral(channel, user, message)
+    }
+
+    fn update_message(&self, channel: &str, ts: &str, message: &Message) -> Result<(), SlackError> {
+        (**self).update_message(channel, ts, message)
+    }
+}
+
+/// Talks to Slack over HTTP, reusing one connection pool for every call.
+pub struct ReqwestClient {
+    client: blocking::Client,
+    base_url: String,
+}
+
+impl ReqwestClient {
+    /// A client for the Web API at `base_url`, which is slack.com's unless the config points it at
+    /// a mock or a proxy.
+    pub fn with_base_url(token: &str, base_url: &str) -> Result<Self, SlackError> {
+        let mut headers = header::HeaderMap::new();
+        let authorization = header::HeaderValue::from_str(&format!("Bearer {}", token))
+            .map_err(|_| SlackError::InvalidResponse("token isn't a valid header value".to_string()))?;
+        headers.insert(header::AUTHORIZATION, authorization);
+        let client = blocking::Client::builder().default_headers(headers).build()?;
+        Ok(ReqwestClient {
+            client,
+            base_url: base_url.trim_end_matches('/').to_string(),
+        })
+    }
+
+    /// Calls a Web API method, waiting out rate limits, and returns the response if it was `ok`.
+    fn call(&self, method: &str, body: &Value) -> Result<Value, SlackError> {
+        let url = format!("{}/{}", self.base_url, method);
+        let mut retries = 0;
+        loop {
+            let response = self.client.post(&url).json(body).send()?;
+            if response.status() == StatusCode::TOO_MANY_REQUESTS {
+                let retry_after = retry_after(response.headers());
+                if retries == MAX_RETRIES {
+                    return Err(SlackError::RateLimited { retry_after });
+                }
+                retries += 1;
+                thread::sleep(Duration::from_secs(retry_after));
+                continue;
+            }
+            let status = response.status();
+            let v: Value = response
+                .json()
+                .map_err(|e| SlackError::InvalidResponse(format!("{} from {}: {}", status, method, e)))?;
+            return match v["ok"].as_bool() {
+                Some(true) => Ok(v),
+                Some(false) => Err(SlackError::Api(
+                    v["error"].as_str().unwrap_or("unknown_error").to_string(),
+                )),
+                None => Err(SlackError::InvalidResponse(format!(
+                    "{} from {} has no ok field",
+                    status, method
+                ))),
+            };
+        }
+    }
+}
+
+fn retry_after(headers: &header::HeaderMap) -> u64 {
+    headers
+        .get(header::RETRY_AFTER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse().ok())
+        .unwrap_or(DEFAULT_RETRY_AFTER)
+        .min(MAX_RETRY_AFTER)
+}
+
+impl SlackClient for ReqwestClient {
+    fn post_message(&self, channel: &str, message: &Message) -> Result<PostedMessage, SlackError> {
+        let v = self.call("chat.postMessage", &message.to_json(channel))?;
+        match (v["channel"].as_str(), v["ts"].as_str()) {
+            (Some(channel), Some(ts)) => Ok(PostedMessage {
+                channel: channel.to_string(),
+                ts: ts.to_string(),
+            }),
+            _ => Err(SlackError::InvalidResponse(
+                "chat.postMessage didn't say where it posted".to_string(),
+            )),
+        }
+    }
+
+    fn post_ephemeral(&self, channel: &str, user: &str, message: &Message) -> Result<(), SlackError> {
+        let mut body = message.to_json(channel);
+        body["user"] = json!(user);
+        self.call("chat.postEphemeral", &body).map(|_| ())
+    }
+
+    fn update_message(&self, channel: &str, ts: &str, message: &Message) -> Result<(), SlackError> {
+        let mut body = Message {
+            thread_ts: None,
+            ..message.clone()
+        }
+        .to_json(channel);
+        body["ts"] = json!(ts);
+        self.call("chat.update", &body).map(|_| ())
+    }
+}
+
+/// A message the fake client was asked to send.
+#[cfg(test)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct SentMessage {
+    pub channel: String,
+    /// Who an ephemeral message was for, or `None` if everyone could see it.
+    pub user: Option<String>,
+    pub text: String,
+    pub blocks: Option<Value>,
+    pub thread_ts: Option<String>,
+    pub broadcast: bool,
+    /// The message this replaced, for `chat.update`.
+    pub updated: Option<String>,
+}
+
+#[cfg(test)]
+impl SentMessage {
+    fn new(channel: &str, user: Option<&str>, message: &Message) -> Self {
+        SentMessage {
+            channel: channel.to_string(),
+            user: user.map(String::from),
+            text: message.text.clone(),
+            blocks: message.blocks.clone(),
+            thread_ts: message.thread_ts.clone(),
+            broadcast: message.reply_broadcast,
+            updated: None,
+        }
+    }
+}
+
+/// Records messages instead of sending them, so the bot can be tested without a network.
+#[cfg(test)]
+#[derive(Default)]
+pub struct FakeSlack {
+    sent: Mutex<Vec<SentMessage>>,
+}
+
+#[cfg(test)]
+impl FakeSlack {
+    pub fn new() -> Self {
+        FakeSlack::default()
+    }
+
+    pub fn sent(&self) -> Vec<SentMessage> {
+        self.sent.lock().unwrap().clone()
+    }
+
+    /// Everything sent so far, leaving the fake empty for the next step of a test.
+    pub fn take(&self) -> Vec<SentMessage> {
+        std::mem::take(&mut *self.sent.lock().unwrap())
+    }
+}
+
+#[cfg(test)]
+impl SlackClient for FakeSlack {
+    fn post_message(&self, channel: &str, message: &Message) -> Result<PostedMessage, SlackError> {
+        let mut sent = self.sent.lock().unwrap();
+        sent.push(SentMessage::new(channel, None, message));
+        Ok(PostedMessage {
+            channel: channel.to_string(),
+            ts: format!("{}.000000", sent.len()),
+        })
+    }
+
+    fn post_ephemeral(&self, channel: &str, user: &str, message: &Message) -> Result<(), SlackError> {
+        self.sent
+            .lock()
+            .unwrap()
+            .push(SentMessage::new(channel, Some(user), message));
+        Ok(())
+    }
+
+    fn update_message(&self, channel: &str, ts: &str, message: &Message) -> Result<(), SlackError> {
+        let mut sent = SentMessage::new(channel, None, message);
+        sent.thread_ts = None;
+        sent.updated = Some(ts.to_string());
+        self.sent.lock().unwrap().push(sent);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    use serde_json::{json, Value};
+
+    use crate::slack::{FakeSlack, Message, PostedMessage, ReqwestClient, SlackClient, SlackError};
+
+    /// A request the mock server received.
+    pub(crate) struct Recorded {
+        pub path: String,
+        pub authorization: String,
+        pub body: Value,
+    }
+
+    /// Serves one canned response per request in order, on a local port, and hands back what it
+    /// was sent once it has answered them all.
+    pub(crate) fn mock_server(responses: Vec<String>) -> (String, thread::JoinHandle<Vec<Recorded>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/api", listener.local_addr().unwrap());
+        let handle = thread::spawn(move || {
+            let mut recorded = Vec::new();
+            for response in responses {
+                let (stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream);
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap();
+                let mut length = 0;
+                let mut authorization = String::new();
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    let line = line.trim_end();
+                    if line.is_empty() {
+                        break;
+                    }
+                    let (name, value) = line.split_once(": ").unwrap();
+                    match name.to_lowercase().as_str() {
+                        "content-length" => length = value.parse().unwrap(),
+                        "authorization" => authorization = value.to_string(),
+                        _ => {}
+                    }
+                }
+                let mut body = vec![0; length];
+                reader.read_exact(&mut body).unwrap();
+                recorded.push(Recorded {
+                    path: request_line.split_whitespace().nth(1).unwrap().to_string(),
+                    authorization,
+                    body: serde_json::from_slice(&body).unwrap(),
+                });
+                reader.get_mut().write_all(response.as_bytes()).unwrap();
+            }
+            recorded
+        });
+        (url, handle)
+    }
+
+    pub(crate) fn http_response(status: &str, headers: &[&str], body: &str) -> String {
+        let mut response = format!(
+            "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n",
+            status,
+            body.len()
+        );
+        for header in headers {
+            response.push_str(header);
+            response.push_str("\r\n");
+        }
+        response.push_str("\r\n");
+        response.push_str(body);
+        response
+    }
+
+    const POSTED: &str = r#"{"ok":true,"channel":"C2147483705","ts":"1503435956.000247"}"#;
+
+    #[test]
+    fn posts_messages() {
+        let (url, server) = mock_server(vec![http_response("200 OK", &[], POSTED)]);
+        let client = ReqwestClient::with_base_url("xoxb-1234", &url).unwrap();
+        let posted = client.post_message("C2147483705", &"Hello".into()).unwrap();
+        assert_eq!(
+            posted,
+            PostedMessage {
+                channel: "C2147483705".to_string(),
+                ts: "1503435956.000247".to_string()
+            }
+        );
+
+        let recorded = server.join().unwrap();
+        assert_eq!(recorded[0].path, "/api/chat.postMessage");
+        assert_eq!(recorded[0].authorization, "Bearer xoxb-1234");
+        assert_eq!(recorded[0].body["channel"], "C2147483705");
+        assert_eq!(recorded[0].body["text"], "Hello");
+    }
+
+    #[test]
+    fn posts_ephemeral_messages() {
+        let (url, server) = mock_server(vec![http_response("200 OK", &[], r#"{"ok":true,"message_ts":"1.2"}"#)]);
+        let client = ReqwestClient::with_base_url("xoxb-1234", &url).unwrap();
+        client
+            .post_ephemeral("C1", "U1", &"Only you can see this".into())
+            .unwrap();
+
+        let recorded = server.join().unwrap();
+        assert_eq!(recorded[0].path, "/api/chat.postEphemeral");
+        assert_eq!(recorded[0].body["user"], "U1");
+    }
+
+    #[test]
+    fn posts_in_threads() {
+        let (url, server) = mock_server(vec![
+            http_response("200 OK", &[], POSTED),
+            http_response("200 OK", &[], POSTED),
+            http_response("200 OK", &[], POSTED),
+        ]);
+        let client = ReqwestClient::with_base_url("xoxb-1234", &url).unwrap();
+        client
+            .post_message("C1", &Message::new("reply").in_thread("1.2"))
+            .unwrap();
+        let mut broadcast = Message::new("done").in_thread("1.2");
+        broadcast.reply_broadcast = true;
+        client.post_message("C1", &broadcast).unwrap();
+        client
+            .update_message("C1", "1.2", &Message::new("edited").in_thread("1.2"))
+            .unwrap();
+
+        let recorded = server.join().unwrap();
+        assert_eq!(recorded[0].body["thread_ts"], "1.2");
+        assert!(recorded[0].body.get("reply_broadcast").is_none());
+        assert_eq!(recorded[1].body["reply_broadcast"], true);
+        assert_eq!(recorded[2].path, "/api/chat.update");
+        assert_eq!(recorded[2].body["ts"], "1.2");
+        assert_eq!(recorded[2].body["text"], "edited");
+        assert!(recorded[2].body.get("thread_ts").is_none());
+    }
+
+    #[test]
+    fn posts_blocks() {
+        let (url, server) = mock_server(vec![http_response("200 OK", &[], POSTED)]);
+        let client = ReqwestClient::with_base_url("xoxb-1234", &url).unwrap();
+        let blocks = json!([{ "type": "section", "text": { "type": "mrkdwn", "text": "*Hello*" } }]);
+        let message = Message::new("Hello").with_blocks(blocks.clone());
+        client.post_message("C2147483705", &message).unwrap();
+
+        let recorded = server.join().unwrap();
+        assert_eq!(recorded[0].body["text"], "Hello");
+        assert_eq!(recorded[0].body["blocks"], blocks);
+    }
+
+    #[test]
+    fn reports_api_errors() {
+        let body = r#"{"ok":false,"error":"channel_not_found"}"#;
+        let (url, server) = mock_server(vec![http_response("200 OK", &[], body)]);
+        let client = ReqwestClient::with_base_url("xoxb-1234", &url).unwrap();
+        match client.post_message("C0", &"Hello".into()) {
+            Err(SlackError::Api(error)) => assert_eq!(error, "channel_not_found"),
+            other => panic!("expected an API error, got {:?}", other),
+        }
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn rejects_non_json_responses() {
+        let (url, server) = mock_server(vec![http_response("502 Bad Gateway", &[], "<html>")]);
+        let client = ReqwestClient::with_base_url("xoxb-1234", &url).unwrap();
+        assert!(matches!(
+            client.post_message("C0", &"Hello".into()),
+            Err(SlackError::InvalidResponse(_))
+        ));
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn retries_when_rate_limited() {
+        let limited = http_response("429 Too Many Requests", &["Retry-After: 0"], "");
+        let (url, server) = mock_server(vec![limited.clone(), limited, http_response("200 OK", &[], POSTED)]);
+        let client = ReqwestClient::with_base_url("xoxb-1234", &url).unwrap();
+        assert!(client.post_message("C2147483705", &"Hello".into()).is_ok());
+        assert_eq!(server.join().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn gives_up_when_still_rate_limited() {
+        let limited = http_response("429 Too Many Requests", &["Retry-After: 0"], "");
+        let (url, server) = mock_server(vec![limited; 4]);
+        let client = ReqwestClient::with_base_url("xoxb-1234", &url).unwrap();
+        assert!(matches!(
+            client.post_message("C0", &"Hello".into()),
+            Err(SlackError::RateLimited { retry_after: 0 })
+        ));
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn fake_records_messages() {
+        let slack = FakeSlack::new();
+        slack.post_message("C1", &"one".into()).unwrap();
+        let posted = slack.post_message("C2", &"two".into()).unwrap();
+        slack.post_ephemeral("C2", "U1", &"three".into()).unwrap();
+        assert_eq!(posted.ts, "2.000000");
+        assert_eq!(slack.sent().len(), 3);
+        let sent = slack.take();
+        assert_eq!(sent[1].text, "two");
+        assert_eq!(sent[1].user, None);
+        assert_eq!(sent[2].user, Some("U1".to_string()));
+        assert!(slack.sent().is_empty());
+    }
 }
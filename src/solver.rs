@@ -0,0 +1,216 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use crate::board::{score, Board, Cell};
+
+/// A next guess, ranked by how much it's expected to narrow down the answer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Suggestion {
+    pub word: String,
+    /// The expected information from the colours this guess would get back, in bits.
+    pub entropy: f64,
+    /// Whether the guess could still be the answer.
+    pub candidate: bool,
+}
+
+/// Encodes a scored row as a number, so the colours different answers would give a guess can be
+/// counted. Rows of the same length with different colours always get different numbers.
+pub fn pattern(row: &[Cell]) -> u32 {
+    row.iter().fold(0, |n, c| {
+        n * 3
+            + match c {
+                Cell::Green(_) => 2,
+                Cell::Yellow(_) => 1,
+                _ => 0,
+            }
+    })
+}
+
+/// Every answer that would have coloured the board's rows exactly the way they are, lowercase.
+pub fn candidates(board: &Board) -> Vec<String> {
+    let guesses = board.guesses();
+    board
+        .dictionary
+        .answers(board.config().word_length)
+        .iter()
+        .map(|w| w.to_lowercase())
+        .filter(|answer| {
+            guesses
+                .iter()
+                .zip(board.rows.iter())
+                .all(|(guess, row)| score(answer, guess) == *row)
+        })
+        .collect()
+}
+
+/// How many bits `guess` is expected to reveal when the answer is equally likely to be any of
+/// `candidates`: the entropy of the distribution of colour patterns it would get back.
+pub fn entropy(guess: &str, candidates: &[String]) -> f64 {
+    let mut counts: HashMap<u32, usize> = HashMap::new();
+    for candidate in candidates {
+        *counts.entry(pattern(&score(candidate, guess))).or_insert(0) += 1;
+    }
+    let total = candidates.len() as f64;
+    counts
+        .values()
+        .map(|&n| {
+            let p = n as f64 / total;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+/// The `n` best next guesses for `board`, best first. Guesses are drawn from the answer list, or
+/// only from the remaining candidates in hard mode so every suggestion is a legal guess. Ties go
+/// to guesses that could still win outright.
+pub fn suggest(board: &Board, n: usize) -> Vec<Suggestion> {
+    if board.config().hard_mode {
+        return suggest_candidates(board, n);
+    }
+    let candidates = candidates(board);
+    if candidates.is_empty() || board.is_over() {
+        return vec![];
+    }
+    let pool: Vec<String> = board
+        .dictionary
+        .answers(board.config().word_length)
+        .iter()
+        .map(|w| w.to_lowercase())
+        .collect();
+    rank(pool, &candidates, n)
+}
+
+/// Like `suggest`, but only guesses that agree with every colour on the board and so could still
+/// be the answer.
+pub fn suggest_candidates(board: &Board, n: usize) -> Vec<Suggestion> {
+    let candidates = candidates(board);
+    if candidates.is_empty() || board.is_over() {
+        return vec![];
+    }
+    rank(candidates.clone(), &candidates, n)
+}
+
+fn rank(pool: Vec<String>, candidates: &[String], n: usize) -> Vec<Suggestion> {
+    let mut suggestions: Vec<Suggestion> = pool
+        .into_iter()
+        .map(|word| Suggestion {
+            entropy: entropy(&word, candidates),
+            candidate: candidates.contains(&word),
+            word,
+        })
+        .collect();
+    suggestions.sort_by(|a, b| {
+        b.entropy
+            .partial_cmp(&a.entropy)
+            .unwrap_or(Ordering::Equal)
+            .then(b.candidate.cmp(&a.candidate))
+            .then(a.word.cmp(&b.word))
+    });
+    suggestions.truncate(n);
+    suggestions
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::{score, Board, BoardConfig};
+    use crate::dictionary::{DictionaryLike, WebDictionary};
+    use crate::solver::{candidates, entropy, pattern, suggest};
+
+    fn board(word: &str) -> Board {
+        Board::new_wasm(word.to_string(), BoardConfig::default())
+    }
+
+    #[test]
+    fn patterns() {
+        assert_eq!(pattern(&score("rusty", "abcde")), 0);
+        assert_eq!(pattern(&score("rusty", "rusty")), 242);
+        assert_ne!(pattern(&score("rusty", "rutsy")), pattern(&score("rusty", "tramp")));
+    }
+
+    #[test]
+    fn candidates_match_every_row() {
+        let mut board = board("rusty");
+        let all = candidates(&board);
+        assert_eq!(all.len(), WebDictionary::new().answers(5).len());
+
+        board.guess("crane").unwrap();
+        board.guess("tulip").unwrap();
+        let remaining = candidates(&board);
+        assert!(remaining.contains(&"rusty".to_string()));
+        assert!(remaining.len() < all.len());
+        for candidate in &remaining {
+            assert_eq!(score(candidate, "crane"), board.rows[0]);
+            assert_eq!(score(candidate, "tulip"), board.rows[1]);
+        }
+    }
+
+    #[test]
+    fn entropy_bounds() {
+        let words: Vec<String> = ["rusty", "crane", "tulip", "whale"]
+            .iter()
+            .map(|w| w.to_string())
+            .collect();
+        assert_eq!(entropy("rusty", &words[..1]), 0.0);
+        // every answer gives a different pattern, so the guess reveals all 2 bits
+        assert!((entropy("crane", &words) - 2.0).abs() < 1e-9);
+        assert!(entropy("zzzzz", &words) < 1e-9);
+    }
+
+    #[test]
+    fn suggestions_are_ranked() {
+        let mut board = board("rusty");
+        board.guess("crane").unwrap();
+        let suggestions = suggest(&board, 5);
+        assert_eq!(suggestions.len(), 5);
+        for pair in suggestions.windows(2) {
+            assert!(pair[0].entropy >= pair[1].entropy);
+        }
+        assert!(suggestions[0].entropy > 0.0);
+    }
+
+    struct Answers(Vec<String>);
+
+    impl DictionaryLike for Answers {
+        fn is_allowed_guess(&self, _word: &str) -> bool {
+            true
+        }
+
+        fn answers(&self, _length: usize) -> &[String] {
+            &self.0
+        }
+    }
+
+    #[test]
+    fn suggests_the_answer_when_its_the_only_one_left() {
+        let answers = ["CRANE", "DUSTY", "MUSTY", "RUSTY"]
+            .iter()
+            .map(|w| w.to_string())
+            .collect();
+        let mut board = Board::with_dictionary("rusty".to_string(), BoardConfig::default(), Box::new(Answers(answers)));
+        board.guess("dusty").unwrap();
+        assert_eq!(candidates(&board), vec!["musty", "rusty"]);
+        board.guess("musty").unwrap();
+        assert_eq!(candidates(&board), vec!["rusty"]);
+        let suggestions = suggest(&board, 3);
+        assert_eq!(suggestions.len(), 3);
+        assert_eq!(suggestions[0].word, "rusty");
+        assert!(suggestions[0].candidate);
+        board.guess("rusty").unwrap();
+        assert!(suggest(&board, 3).is_empty());
+    }
+
+    #[test]
+    fn hard_mode_only_suggests_candidates() {
+        let config = BoardConfig {
+            hard_mode: true,
+            ..BoardConfig::default()
+        };
+        let mut board = Board::new_wasm("rusty".to_string(), config);
+        board.guess("crane").unwrap();
+        let remaining = candidates(&board);
+        for suggestion in suggest(&board, 10) {
+            assert!(suggestion.candidate);
+            assert!(remaining.contains(&suggestion.word));
+        }
+    }
+}
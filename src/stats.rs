@@ -0,0 +1,405 @@
+use chrono::{Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::JsValue;
+
+use crate::board::{Board, Cell};
+use crate::theme::Theme;
+#[cfg(target_arch = "wasm32")]
+use crate::traits::WebComponent;
+
+#[cfg(not(target_arch = "wasm32"))]
+use crossterm::{queue, style};
+#[cfg(not(target_arch = "wasm32"))]
+use std::fs;
+#[cfg(not(target_arch = "wasm32"))]
+use std::io::{self, Write};
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::Path;
+
+/// The histogram always has bars for the usual six guesses, and more if longer games were won.
+const MIN_BARS: usize = 6;
+/// The longest bar, in emoji in Slack and in columns in the terminal.
+const SLACK_BAR_WIDTH: usize = 10;
+#[cfg(not(target_arch = "wasm32"))]
+const TERMINAL_BAR_WIDTH: usize = 30;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Mode {
+    /// The daily puzzle with this number.
+    Daily(u32),
+    /// A random word, or one picked on the command line.
+    Free,
+}
+
+/// One finished game.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GameRecord {
+    pub date: NaiveDate,
+    pub answer: String,
+    pub guesses: usize,
+    pub max_guesses: usize,
+    pub won: bool,
+    pub mode: Mode,
+    pub hard_mode: bool,
+    pub hints: usize,
+}
+
+impl GameRecord {
+    /// The record of `board`, or `None` while it's still being played.
+    pub fn from_board(board: &Board) -> Option<Self> {
+        let answer = board.reveal_answer()?;
+        let finished = board.finished_at().unwrap_or_else(|| board.started_at());
+        Some(GameRecord {
+            date: finished.with_timezone(&Local).date_naive(),
+            answer: answer.to_string(),
+            guesses: board.guesses_used(),
+            max_guesses: board.rows.len(),
+            won: board.has_won(),
+            mode: board.puzzle_number().map_or(Mode::Free, Mode::Daily),
+            hard_mode: board.config().hard_mode,
+            hints: board.hints().len(),
+        })
+    }
+}
+
+/// Every game a player has finished, oldest first.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Stats {
+    games: Vec<GameRecord>,
+}
+
+/// What a player's games add up to.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Summary {
+    pub played: u32,
+    pub won: u32,
+    /// Wins in a row, up to the latest game.
+    pub current_streak: u32,
+    pub max_streak: u32,
+    /// How many games were won in one guess, in two and so on.
+    pub distribution: Vec<u32>,
+    /// How many guesses the latest game was won in, so its bar can stand out.
+    pub last_win: Option<usize>,
+}
+
+impl Stats {
+    /// Adds a finished game. A daily puzzle only counts the first time, so replaying one can't pad
+    /// the numbers; returns whether it was counted.
+    pub fn record(&mut self, game: GameRecord) -> bool {
+        if matches!(game.mode, Mode::Daily(_)) && self.games.iter().any(|g| g.mode == game.mode) {
+            return false;
+        }
+        self.games.push(game);
+        true
+    }
+
+    /// Writes the stats to `path` as JSON, creating its directory if needed.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let path = path.as_ref();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        // every game played is in here, so a crash mid-write mustn't be able to lose them
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, serde_json::to_string(self)?)?;
+        fs::rename(tmp, path)
+    }
+
+    /// Reads stats written by `save`.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    pub fn summary(&self) -> Summary {
+        let mut summary = Summary {
+            distribution: vec![0; MIN_BARS],
+            ..Summary::default()
+        };
+        for game in &self.games {
+            summary.played += 1;
+            if !game.won {
+                summary.current_streak = 0;
+                continue;
+            }
+            summary.won += 1;
+            summary.current_streak += 1;
+            summary.max_streak = summary.max_streak.max(summary.current_streak);
+            if game.guesses > summary.distribution.len() {
+                summary.distribution.resize(game.guesses, 0);
+            }
+            if let Some(bar) = game.guesses.checked_sub(1) {
+                summary.distribution[bar] += 1;
+            }
+        }
+        summary.last_win = self.games.last().filter(|g| g.won).map(|g| g.guesses);
+        summary
+    }
+
+    /// Daily puzzles won in a row, going into or coming out of `puzzle`. Missing a day breaks the
+    /// streak as surely as losing does.
+    pub fn daily_streak(&self, puzzle: u32) -> u32 {
+        let mut daily: Vec<(u32, bool)> = self
+            .games
+            .iter()
+            .filter_map(|g| match g.mode {
+                Mode::Daily(p) if p <= puzzle => Some((p, g.won)),
+                _ => None,
+            })
+            .collect();
+        daily.sort_by_key(|(p, _)| *p);
+
+        let mut streak = 0;
+        let mut next = puzzle;
+        for (p, won) in daily.into_iter().rev() {
+            // the latest can be today's or yesterday's, the rest have to follow on
+            let follows = if streak == 0 { p + 1 >= next } else { p + 1 == next };
+            if !won || !follows {
+                break;
+            }
+            streak += 1;
+            next = p;
+        }
+        streak
+    }
+}
+
+impl Summary {
+    pub fn win_percentage(&self) -> u32 {
+        (self.won * 100).checked_div(self.played).unwrap_or_default()
+    }
+
+    /// "Played 12 · Win % 83 · Current streak 4 · Max streak 6"
+    pub fn headline(&self) -> String {
+        format!(
+            "Played {} · Win % {} · Current streak {} · Max streak {}",
+            self.played,
+            self.win_percentage(),
+            self.current_streak,
+            self.max_streak
+        )
+    }
+
+    /// Each bar's length out of `width`, the most common number of guesses filling it. Bars for
+    /// any wins at all are at least 1 long.
+    fn bars(&self, width: usize) -> Vec<usize> {
+        let most = self.distribution.iter().copied().max().unwrap_or_default().max(1) as usize;
+        self.distribution
+            .iter()
+            .map(|&count| match count as usize {
+                0 => 0,
+                count => (count * width / most).max(1),
+            })
+            .collect()
+    }
+
+    /// The tile colour of the bar for `guesses`: the latest win's stands out.
+    fn bar_cell(&self, guesses: usize) -> Cell {
+        if self.last_win == Some(guesses) {
+            Cell::Green(' ')
+        } else {
+            Cell::Gray(' ')
+        }
+    }
+
+    /// Slack mrkdwn, with the histogram drawn in the theme's squares.
+    pub fn slack(&self, theme: Theme) -> String {
+        let mut message = format!("*Your stats*\n{}\n\n*Guess distribution*", self.headline());
+        for (idx, (count, bar)) in self.distribution.iter().zip(self.bars(SLACK_BAR_WIDTH)).enumerate() {
+            let square = theme.emoji(&self.bar_cell(idx + 1));
+            message.push_str(&format!("\n`{:>2}` {}{}", idx + 1, square.repeat(bar), count));
+        }
+        message
+    }
+
+    /// Writes the summary to a terminal, with bars in the theme's colours.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn draw<W: Write>(&self, out: &mut W, theme: Theme) -> io::Result<()> {
+        queue!(
+            out,
+            style::Print(format!("{}\n\nGuess distribution\n", self.headline()))
+        )?;
+        for (idx, (count, bar)) in self.distribution.iter().zip(self.bars(TERMINAL_BAR_WIDTH)).enumerate() {
+            let count = count.to_string();
+            // the count sits at the end of its bar, which needs to be long enough to hold it
+            let width = bar.max(count.len() + 1);
+            let (background, foreground) = theme.terminal(&self.bar_cell(idx + 1));
+            queue!(
+                out,
+                style::Print(format!("{:>2} ", idx + 1)),
+                style::SetBackgroundColor(background),
+                style::SetForegroundColor(foreground),
+                style::Print(format!("{:>width$} ", count, width = width)),
+                style::ResetColor,
+                style::Print("\n")
+            )?;
+        }
+        out.flush()
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+impl WebComponent for Summary {
+    fn to_dom(&self, document: &web_sys::Document, theme: Theme) -> Result<web_sys::Element, JsValue> {
+        let container: web_sys::Element = document.create_element("div")?;
+        let headline: web_sys::Element = document.create_element("p")?;
+        headline.set_text_content(Some(&self.headline()));
+        container.append_child(&headline)?;
+        for (idx, (count, bar)) in self.distribution.iter().zip(self.bars(100)).enumerate() {
+            let row: web_sys::Element = document.create_element("div")?;
+            row.set_attribute("style", "display: flex; align-items: center; margin: 2px 0")?;
+            let label: web_sys::Element = document.create_element("span")?;
+            label.set_attribute("style", "width: 20px")?;
+            label.set_text_content(Some(&(idx + 1).to_string()));
+            row.append_child(&label)?;
+            let bar_div: web_sys::Element = document.create_element("div")?;
+            bar_div.set_attribute(
+                "style",
+                &format!(
+                    "width: {}%; min-width: 20px; text-align: right; padding: 0 4px; {}",
+                    bar,
+                    theme.css(&self.bar_cell(idx + 1)).style()
+                ),
+            )?;
+            bar_div.set_text_content(Some(&count.to_string()));
+            row.append_child(&bar_div)?;
+            container.append_child(&row)?;
+        }
+        Ok(container)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use crate::board::{Board, BoardConfig};
+    use crate::dictionary::WebDictionary;
+    use crate::stats::{GameRecord, Mode, Stats};
+    use crate::theme::Theme;
+
+    fn game(mode: Mode, guesses: usize, won: bool) -> GameRecord {
+        GameRecord {
+            date: NaiveDate::from_ymd_opt(2022, 8, 5).unwrap(),
+            answer: "rusty".to_string(),
+            guesses,
+            max_guesses: 6,
+            won,
+            mode,
+            hard_mode: false,
+            hints: 0,
+        }
+    }
+
+    fn stats(games: &[(usize, bool)]) -> Stats {
+        let mut stats = Stats::default();
+        for &(guesses, won) in games {
+            stats.record(game(Mode::Free, guesses, won));
+        }
+        stats
+    }
+
+    #[test]
+    fn records_finished_games() {
+        let mut board = Board::with_dictionary(
+            "rusty".to_string(),
+            BoardConfig {
+                hard_mode: true,
+                ..BoardConfig::default()
+            },
+            Box::new(WebDictionary::new()),
+        );
+        board.guess("trust").unwrap();
+        assert_eq!(GameRecord::from_board(&board), None);
+        board.hint();
+        board.guess("rusty").unwrap();
+        let record = GameRecord::from_board(&board).unwrap();
+        assert_eq!(record.answer, "rusty");
+        assert_eq!((record.guesses, record.max_guesses, record.won), (2, 6, true));
+        assert_eq!((record.mode, record.hard_mode, record.hints), (Mode::Free, true, 1));
+    }
+
+    #[test]
+    fn sums_up_games() {
+        let summary = stats(&[(3, true), (4, true), (6, false), (3, true), (2, true), (3, true)]).summary();
+        assert_eq!((summary.played, summary.won, summary.win_percentage()), (6, 5, 83));
+        assert_eq!((summary.current_streak, summary.max_streak), (3, 3));
+        assert_eq!(summary.distribution, vec![0, 1, 3, 1, 0, 0]);
+        assert_eq!(summary.last_win, Some(3));
+        assert_eq!(
+            summary.headline(),
+            "Played 6 · Win % 83 · Current streak 3 · Max streak 3"
+        );
+
+        let summary = stats(&[(2, true), (2, true), (6, false)]).summary();
+        assert_eq!((summary.current_streak, summary.max_streak), (0, 2));
+        assert_eq!(summary.last_win, None);
+        assert_eq!(stats(&[(8, true)]).summary().distribution.len(), 8);
+        assert_eq!(Stats::default().summary().win_percentage(), 0);
+    }
+
+    #[test]
+    fn counts_each_daily_puzzle_once() {
+        let mut stats = Stats::default();
+        assert!(stats.record(game(Mode::Daily(412), 3, true)));
+        assert!(!stats.record(game(Mode::Daily(412), 1, true)));
+        assert!(stats.record(game(Mode::Free, 3, true)));
+        assert!(stats.record(game(Mode::Free, 3, true)));
+        assert_eq!(stats.games.len(), 3);
+    }
+
+    #[test]
+    fn daily_streaks() {
+        let mut stats = Stats::default();
+        for puzzle in 410..=412 {
+            stats.record(game(Mode::Daily(puzzle), 3, true));
+        }
+        // a game that isn't the daily puzzle doesn't get in the way
+        stats.record(game(Mode::Free, 6, false));
+        assert_eq!(stats.daily_streak(412), 3);
+        assert_eq!(stats.daily_streak(413), 3);
+        assert_eq!(stats.daily_streak(414), 0);
+        assert_eq!(stats.daily_streak(411), 2);
+        stats.record(game(Mode::Daily(414), 3, true));
+        assert_eq!(stats.daily_streak(414), 1);
+        stats.record(game(Mode::Daily(415), 6, false));
+        assert_eq!(stats.daily_streak(415), 0);
+        assert_eq!(Stats::default().daily_streak(415), 0);
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn save_and_load() {
+        let dir = std::env::temp_dir().join(format!("wordle-stats-{}", std::process::id()));
+        let path = dir.join("saves").join("stats.json");
+        let stats = stats(&[(3, true), (6, false)]);
+        stats.save(&path).unwrap();
+        let loaded = Stats::load(&path).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(loaded, stats);
+        assert!(Stats::load(&path).is_err());
+    }
+
+    #[test]
+    fn renders_the_histogram() {
+        let summary = stats(&[(3, true), (3, true), (4, true)]).summary();
+        let slack = summary.slack(Theme::Standard);
+        let lines: Vec<&str> = slack.lines().collect();
+        assert_eq!(lines[0], "*Your stats*");
+        assert_eq!(lines[3], "*Guess distribution*");
+        assert_eq!(lines[5], "` 2` 0");
+        assert_eq!(lines[6], "` 3` ⬜⬜⬜⬜⬜⬜⬜⬜⬜⬜2");
+        assert_eq!(lines[7], "` 4` 🟩🟩🟩🟩🟩1");
+        assert_eq!(lines.len(), 10);
+        assert!(summary.slack(Theme::HighContrast).contains("🟧🟧🟧🟧🟧1"));
+
+        let mut out = Vec::new();
+        summary.draw(&mut out, Theme::Standard).unwrap();
+        let screen = String::from_utf8(out).unwrap();
+        assert!(screen.starts_with("Played 3 · Win % 100"));
+        assert!(screen.contains(&format!("{:>30} ", 2)));
+    }
+}
@@ -0,0 +1,266 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::board::Cell;
+use crate::keyboard::LetterStatus;
+
+#[cfg(not(target_arch = "wasm32"))]
+use crossterm::style::Color;
+
+/// The colours a board is drawn in. Every renderer asks the theme rather than picking its own, so
+/// the terminal, the web page and the emoji in a share always agree.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Theme {
+    /// Green and yellow, in the terminal's own palette.
+    #[default]
+    Standard,
+    /// Orange and blue, which stay apart for colour-blind players.
+    HighContrast,
+    Dark,
+    Light,
+}
+
+/// How a tile or key looks on the web page, as CSS colours.
+#[cfg(any(target_arch = "wasm32", test))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Css {
+    pub background: &'static str,
+    pub text: &'static str,
+    pub border: &'static str,
+}
+
+#[cfg(target_arch = "wasm32")]
+impl Css {
+    /// The declarations for a `style` attribute.
+    pub fn style(&self) -> String {
+        format!(
+            "background-color: {}; color: {}; border-color: {};",
+            self.background, self.text, self.border
+        )
+    }
+}
+
+impl Theme {
+    pub const ALL: [Theme; 4] = [Theme::Standard, Theme::HighContrast, Theme::Dark, Theme::Light];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Theme::Standard => "standard",
+            Theme::HighContrast => "high-contrast",
+            Theme::Dark => "dark",
+            Theme::Light => "light",
+        }
+    }
+
+    /// "standard, high-contrast, dark or light"
+    pub fn names() -> String {
+        let names: Vec<&str> = Theme::ALL.iter().map(Theme::name).collect();
+        format!("{} or {}", names[..names.len() - 1].join(", "), names[names.len() - 1])
+    }
+
+    /// The square for a tile in a share or a Slack grid.
+    pub fn emoji(&self, cell: &Cell) -> &'static str {
+        match (self, cell) {
+            (Theme::HighContrast, Cell::Green(_)) => "🟧",
+            (Theme::HighContrast, Cell::Yellow(_)) => "🟦",
+            (_, Cell::Green(_)) => "🟩",
+            (_, Cell::Yellow(_)) => "🟨",
+            // dark tiles for wrong letters, so empty ones need to look different
+            (Theme::Dark, Cell::Gray(_)) => "⬛",
+            (Theme::Dark, Cell::Empty) => "🔲",
+            (_, Cell::Gray(_)) => "⬜",
+            (_, Cell::Empty) => "⬛",
+        }
+    }
+
+    /// The square marking a key in Slack's keyboard. Letters that aren't in the word are struck
+    /// through instead, and unused ones left plain.
+    pub fn key_emoji(&self, status: LetterStatus) -> Option<&'static str> {
+        match status {
+            LetterStatus::Correct => Some(self.emoji(&Cell::Green(' '))),
+            LetterStatus::Present => Some(self.emoji(&Cell::Yellow(' '))),
+            LetterStatus::Absent | LetterStatus::Unknown => None,
+        }
+    }
+
+    /// A tile's background and letter colours in the terminal.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn terminal(&self, cell: &Cell) -> (Color, Color) {
+        const ORANGE: Color = Color::Rgb { r: 245, g: 121, b: 58 };
+        const BLUE: Color = Color::Rgb { r: 133, g: 192, b: 249 };
+        let white = Color::White;
+        match (self, cell) {
+            (Theme::Standard, Cell::Green(_)) => (Color::Green, Color::Black),
+            (Theme::Standard, Cell::Yellow(_)) => (Color::Yellow, Color::Black),
+            (Theme::Standard | Theme::HighContrast, Cell::Gray(_)) => (Color::Grey, Color::Black),
+            (Theme::Standard | Theme::HighContrast, Cell::Empty) => (Color::Black, Color::DarkGrey),
+            (Theme::HighContrast, Cell::Green(_)) => (ORANGE, Color::Black),
+            (Theme::HighContrast, Cell::Yellow(_)) => (BLUE, Color::Black),
+            (Theme::Dark, Cell::Green(_)) => (Color::Rgb { r: 83, g: 141, b: 78 }, white),
+            (Theme::Dark, Cell::Yellow(_)) => (Color::Rgb { r: 181, g: 159, b: 59 }, white),
+            (Theme::Dark, Cell::Gray(_)) => (Color::Rgb { r: 58, g: 58, b: 60 }, white),
+            (Theme::Dark, Cell::Empty) => (Color::Rgb { r: 18, g: 18, b: 19 }, Color::Rgb { r: 58, g: 58, b: 60 }),
+            (Theme::Light, Cell::Green(_)) => (Color::Rgb { r: 106, g: 170, b: 100 }, white),
+            (Theme::Light, Cell::Yellow(_)) => (Color::Rgb { r: 201, g: 180, b: 88 }, white),
+            (Theme::Light, Cell::Gray(_)) => (Color::Rgb { r: 120, g: 124, b: 126 }, white),
+            (Theme::Light, Cell::Empty) => (white, Color::Rgb { r: 211, g: 214, b: 218 }),
+        }
+    }
+
+    /// A key's background and letter colours in the terminal.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn terminal_key(&self, status: LetterStatus) -> (Color, Color) {
+        match (self, status) {
+            (Theme::Standard | Theme::HighContrast, LetterStatus::Unknown) => (Color::Grey, Color::Black),
+            (Theme::Standard | Theme::HighContrast, LetterStatus::Absent) => (Color::DarkGrey, Color::Black),
+            (Theme::Dark, LetterStatus::Unknown) => (Color::Rgb { r: 129, g: 131, b: 132 }, Color::White),
+            (Theme::Light, LetterStatus::Unknown) => (Color::Rgb { r: 211, g: 214, b: 218 }, Color::Black),
+            (_, status) => self.terminal(&status_cell(status)),
+        }
+    }
+
+    /// A tile's colours on the web page.
+    #[cfg(any(target_arch = "wasm32", test))]
+    pub fn css(&self, cell: &Cell) -> Css {
+        let css = |background, text, border| Css {
+            background,
+            text,
+            border,
+        };
+        match (self, cell) {
+            (Theme::Standard, Cell::Green(_)) => css("#6aaa64", "black", "#6aaa64"),
+            (Theme::Standard, Cell::Yellow(_)) => css("#c9b458", "black", "#c9b458"),
+            (Theme::Standard | Theme::HighContrast, Cell::Gray(_)) => css("#86888a", "black", "#86888a"),
+            (Theme::Standard | Theme::HighContrast, Cell::Empty) => css("transparent", "black", "#d3d6da"),
+            (Theme::HighContrast, Cell::Green(_)) => css("#f5793a", "black", "#f5793a"),
+            (Theme::HighContrast, Cell::Yellow(_)) => css("#85c0f9", "black", "#85c0f9"),
+            (Theme::Dark, Cell::Green(_)) => css("#538d4e", "white", "#538d4e"),
+            (Theme::Dark, Cell::Yellow(_)) => css("#b59f3b", "white", "#b59f3b"),
+            (Theme::Dark, Cell::Gray(_)) => css("#3a3a3c", "white", "#3a3a3c"),
+            (Theme::Dark, Cell::Empty) => css("#121213", "white", "#3a3a3c"),
+            (Theme::Light, Cell::Green(_)) => css("#6aaa64", "white", "#6aaa64"),
+            (Theme::Light, Cell::Yellow(_)) => css("#c9b458", "white", "#c9b458"),
+            (Theme::Light, Cell::Gray(_)) => css("#787c7e", "white", "#787c7e"),
+            (Theme::Light, Cell::Empty) => css("#ffffff", "black", "#d3d6da"),
+        }
+    }
+
+    /// A key's colours on the web page.
+    #[cfg(any(target_arch = "wasm32", test))]
+    pub fn css_key(&self, status: LetterStatus) -> Css {
+        match (self, status) {
+            (Theme::Dark, LetterStatus::Unknown) => Css {
+                background: "#818384",
+                text: "white",
+                border: "#818384",
+            },
+            (_, LetterStatus::Unknown) => Css {
+                background: "#d3d6da",
+                text: "black",
+                border: "#d3d6da",
+            },
+            (_, status) => self.css(&status_cell(status)),
+        }
+    }
+
+    /// The page behind the board on the web.
+    #[cfg(target_arch = "wasm32")]
+    pub fn css_page(&self) -> Css {
+        match self {
+            Theme::Dark => Css {
+                background: "#121213",
+                text: "white",
+                border: "#3a3a3c",
+            },
+            _ => Css {
+                background: "#ffffff",
+                text: "black",
+                border: "#d3d6da",
+            },
+        }
+    }
+}
+
+/// A tile coloured the way a key with `status` is.
+fn status_cell(status: LetterStatus) -> Cell {
+    match status {
+        LetterStatus::Correct => Cell::Green(' '),
+        LetterStatus::Present => Cell::Yellow(' '),
+        LetterStatus::Absent => Cell::Gray(' '),
+        LetterStatus::Unknown => Cell::Empty,
+    }
+}
+
+impl FromStr for Theme {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.trim().to_lowercase().replace('_', "-");
+        match name.as_str() {
+            "high-contrast" | "highcontrast" | "contrast" | "colour-blind" | "color-blind" => Ok(Theme::HighContrast),
+            name => Theme::ALL
+                .iter()
+                .find(|theme| theme.name() == name)
+                .copied()
+                .ok_or_else(|| format!("unknown theme {:?}, expected one of {}", s.trim(), Theme::names())),
+        }
+    }
+}
+
+impl fmt::Display for Theme {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::Cell;
+    use crate::keyboard::LetterStatus;
+    use crate::theme::Theme;
+
+    #[test]
+    fn parses_names() {
+        for theme in Theme::ALL {
+            assert_eq!(theme.name().parse(), Ok(theme));
+            assert_eq!(theme.to_string(), theme.name());
+        }
+        assert_eq!(" High_Contrast ".parse(), Ok(Theme::HighContrast));
+        assert_eq!("colour-blind".parse(), Ok(Theme::HighContrast));
+        assert_eq!(
+            "neon".parse::<Theme>(),
+            Err("unknown theme \"neon\", expected one of standard, high-contrast, dark or light".to_string())
+        );
+        assert_eq!(
+            serde_json::to_string(&Theme::HighContrast).unwrap(),
+            "\"high-contrast\""
+        );
+    }
+
+    #[test]
+    fn every_theme_tells_tiles_apart() {
+        let cells = [Cell::Green('a'), Cell::Yellow('a'), Cell::Gray('a'), Cell::Empty];
+        for theme in Theme::ALL {
+            for (i, a) in cells.iter().enumerate() {
+                for b in &cells[i + 1..] {
+                    assert_ne!(theme.emoji(a), theme.emoji(b), "{} {:?} {:?}", theme, a, b);
+                    assert_ne!(theme.css(a).background, theme.css(b).background, "{}", theme);
+                    assert_ne!(theme.terminal(a).0, theme.terminal(b).0, "{}", theme);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn high_contrast_is_orange_and_blue() {
+        let theme = Theme::HighContrast;
+        assert_eq!(theme.emoji(&Cell::Green('a')), "🟧");
+        assert_eq!(theme.key_emoji(LetterStatus::Present), Some("🟦"));
+        assert_eq!(theme.key_emoji(LetterStatus::Absent), None);
+        assert_eq!(theme.css_key(LetterStatus::Correct).background, "#f5793a");
+        assert_eq!(Theme::Standard.css_key(LetterStatus::Unknown).background, "#d3d6da");
+    }
+}
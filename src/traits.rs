@@ -1,6 +1,8 @@
 use wasm_bindgen::JsValue;
 use web_sys::{Document, Element};
 
+use crate::theme::Theme;
+
 pub trait WebComponent {
-    fn to_dom(&self, document: &Document) -> Result<Element, JsValue>;
+    fn to_dom(&self, document: &Document, theme: Theme) -> Result<Element, JsValue>;
 }
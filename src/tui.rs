@@ -0,0 +1,542 @@
+use std::io::{self, Write};
+use std::panic;
+use std::time::{Duration, Instant};
+
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyModifiers};
+use crossterm::style::{self, Color};
+use crossterm::terminal::{self, ClearType};
+use crossterm::{cursor, execute, queue};
+
+use crate::animation::{Animation, Kind, TileFrame, FRAME};
+use crate::board::{Board, Cell, GuessError};
+use crate::keyboard::KEYBOARD_ROWS;
+use crate::solver;
+use crate::theme::Theme;
+
+/// How long a message stays on screen.
+const TOAST_DURATION: Duration = Duration::from_secs(2);
+/// How long to wait for a key before checking whether a message has had its time.
+const TICK: Duration = Duration::from_millis(100);
+/// "  A  ", the same tiles as `Board::print`.
+const TILE_WIDTH: u16 = 5;
+const HELP: &str = "Enter to guess · ? for a hint · Tab for a suggestion · Esc to quit";
+
+/// How the game looks, from the command line and the player's saved preferences.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Options {
+    pub animations: bool,
+    pub theme: Theme,
+}
+
+/// The game being played, what's been typed into the active row and any message on screen.
+pub struct App {
+    board: Board,
+    input: String,
+    toast: Option<(String, Instant)>,
+    animations: bool,
+    animation: Option<Animation>,
+    theme: Theme,
+    quit: bool,
+}
+
+impl App {
+    pub fn new(board: Board) -> Self {
+        App {
+            board,
+            input: String::new(),
+            toast: None,
+            animations: false,
+            animation: None,
+            theme: Theme::default(),
+            quit: false,
+        }
+    }
+
+    /// Turns the reveal, shake and bounce on or off; they're off unless asked for.
+    pub fn animated(mut self, animations: bool) -> Self {
+        self.animations = animations;
+        self
+    }
+
+    pub fn themed(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    pub fn into_board(self) -> Board {
+        self.board
+    }
+
+    pub fn input(&self) -> &str {
+        &self.input
+    }
+
+    pub fn toast_message(&self) -> Option<&str> {
+        self.toast.as_ref().map(|(message, _)| message.as_str())
+    }
+
+    pub fn animation(&self) -> Option<&Animation> {
+        self.animation.as_ref()
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.quit
+    }
+
+    /// Shows `message` for a couple of seconds from `now`.
+    pub fn toast<S: Into<String>>(&mut self, message: S, now: Instant) {
+        self.toast = Some((message.into(), now + TOAST_DURATION));
+    }
+
+    /// Takes down a message that's had its time and moves any animation on, returning whether the
+    /// screen needs redrawing.
+    pub fn tick(&mut self, now: Instant) -> bool {
+        let mut redraw = match &self.toast {
+            Some((_, until)) if *until <= now => {
+                self.toast = None;
+                true
+            }
+            _ => false,
+        };
+        if let Some(animation) = self.animation {
+            redraw = true;
+            if animation.is_finished(now) {
+                // the winning row bounces once its colours are all showing
+                self.animation = match animation.kind {
+                    Kind::Reveal if self.board.has_won() => {
+                        Some(Animation::bounce(animation.row, self.board.config().word_length, now))
+                    }
+                    _ => None,
+                };
+            }
+        }
+        redraw
+    }
+
+    /// Whether to wait for the next frame rather than the next key.
+    pub fn is_animating(&self) -> bool {
+        self.animation.is_some()
+    }
+
+    fn animate(&mut self, animation: Animation) {
+        if self.animations {
+            self.animation = Some(animation);
+        }
+    }
+
+    /// Handles a key press, returning whether the game changed and should be saved.
+    pub fn handle_key(&mut self, key: KeyEvent, now: Instant) -> bool {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            // raw mode turns Ctrl-C into a key press rather than a signal
+            KeyCode::Char('c') if ctrl => self.quit = true,
+            KeyCode::Esc => self.quit = true,
+            KeyCode::Enter if self.board.is_over() => self.quit = true,
+            _ if self.board.is_over() => {}
+            KeyCode::Char('?') => return self.hint(now),
+            KeyCode::Tab => self.suggest(now),
+            // letters past the end of the row are dropped, like on a full row in the original
+            KeyCode::Char(c)
+                if c.is_ascii_alphabetic() && !ctrl && self.input.len() < self.board.config().word_length =>
+            {
+                self.input.push(c.to_ascii_lowercase());
+            }
+            KeyCode::Backspace => {
+                self.input.pop();
+            }
+            KeyCode::Enter => return self.submit(now),
+            _ => {}
+        }
+        false
+    }
+
+    fn submit(&mut self, now: Instant) -> bool {
+        let row = self.board.guesses_used();
+        match self.board.guess(&self.input) {
+            Ok(_) => {
+                self.input.clear();
+                self.animate(Animation::reveal(row, self.board.config().word_length, now));
+                true
+            }
+            Err(e) => {
+                self.toast(short_message(&e), now);
+                self.animate(Animation::shake(row, now));
+                false
+            }
+        }
+    }
+
+    fn hint(&mut self, now: Instant) -> bool {
+        match self.board.hint() {
+            Some(hint) => {
+                self.toast(hint.to_string(), now);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn suggest(&mut self, now: Instant) {
+        let message = match solver::suggest(&self.board, 1).first() {
+            Some(suggestion) => format!(
+                "Try {} ({:.2} bits)",
+                suggestion.word.to_uppercase(),
+                suggestion.entropy.abs()
+            ),
+            None => "The solver is stumped!".to_string(),
+        };
+        self.toast(message, now);
+    }
+}
+
+/// The original game's terse messages for the mistakes everyone makes, the full explanation for the
+/// rest.
+fn short_message(e: &GuessError) -> String {
+    match e {
+        GuessError::TooShort { .. } => "Not enough letters".to_string(),
+        GuessError::NotInDictionary(_) => "Not in word list".to_string(),
+        e => e.to_string(),
+    }
+}
+
+/// Draws the whole screen as it looks at `now`, `size` being the terminal's columns and rows.
+pub fn draw<W: Write>(app: &App, out: &mut W, size: (u16, u16), now: Instant) -> io::Result<()> {
+    let (width, height) = size;
+    let board = &app.board;
+    queue!(out, style::ResetColor, terminal::Clear(ClearType::All))?;
+
+    let mut title = match board.puzzle_number() {
+        Some(puzzle) => format!("WORDLE {}", puzzle),
+        None => "WORDLE".to_string(),
+    };
+    if board.config().hard_mode {
+        title.push_str(" · hard mode");
+    }
+    centered(out, width, 0, &title)?;
+
+    let left = width.saturating_sub(board.config().word_length as u16 * TILE_WIDTH) / 2;
+    let active = if board.is_over() {
+        None
+    } else {
+        Some(board.guesses_used())
+    };
+    for (idx, row) in board.rows.iter().enumerate() {
+        let offset = app.animation().map_or(0, |animation| animation.offset(idx, now));
+        let row_left = (left as i16 + offset).max(0) as u16;
+        for (position, cell) in row.iter().enumerate() {
+            let frame = app
+                .animation()
+                .map_or(TileFrame::Shown, |animation| animation.tile(idx, position, now));
+            let y = match frame {
+                TileFrame::Raised => 1 + idx as u16,
+                _ => 2 + idx as u16,
+            };
+            queue!(out, cursor::MoveTo(row_left + position as u16 * TILE_WIDTH, y))?;
+            if Some(idx) == active {
+                let letter = app.input().chars().nth(position).unwrap_or(' ');
+                tile(out, Color::DarkGrey, Color::White, letter)?;
+                continue;
+            }
+            match (frame, cell) {
+                (TileFrame::Hidden, Cell::Green(letter) | Cell::Yellow(letter) | Cell::Gray(letter)) => {
+                    tile(out, Color::DarkGrey, Color::White, *letter)?
+                }
+                // edge on, halfway through turning over
+                (TileFrame::Edge, _) => tile(out, Color::Black, Color::White, '─')?,
+                (_, cell) => {
+                    let (background, foreground) = app.theme.terminal(cell);
+                    let letter = match cell {
+                        Cell::Green(letter) | Cell::Yellow(letter) | Cell::Gray(letter) => *letter,
+                        Cell::Empty => '·',
+                    };
+                    tile(out, background, foreground, letter)?
+                }
+            }
+        }
+    }
+
+    let mut top = 3 + board.rows.len() as u16;
+    if let Some(message) = app.toast_message() {
+        queue!(out, style::SetAttribute(style::Attribute::Reverse))?;
+        centered(out, width, top, &format!(" {} ", message))?;
+        queue!(out, style::SetAttribute(style::Attribute::Reset))?;
+    }
+    top += 2;
+
+    let revealing = matches!(app.animation(), Some(animation) if animation.kind == Kind::Reveal);
+    if board.is_over() && !revealing {
+        // the game over panel takes the keyboard's place
+        let mut lines = vec![board.result_message().unwrap_or_default(), String::new()];
+        lines.extend(board.share(app.theme).lines().map(String::from));
+        lines.push(String::new());
+        lines.push("Press Enter to quit".to_string());
+        for (idx, line) in lines.iter().enumerate() {
+            centered(out, width, top + idx as u16, line)?;
+        }
+    } else {
+        let keyboard_width = KEYBOARD_ROWS[0].len() as u16 * 4;
+        board
+            .keyboard()
+            .draw(out, width.saturating_sub(keyboard_width) / 2, top, app.theme)?;
+        centered(out, width, height.saturating_sub(1).max(top + 4), HELP)?;
+    }
+    queue!(out, style::ResetColor)?;
+    out.flush()
+}
+
+fn tile<W: Write>(out: &mut W, background: Color, foreground: Color, letter: char) -> io::Result<()> {
+    queue!(
+        out,
+        style::SetBackgroundColor(background),
+        style::SetForegroundColor(foreground),
+        style::Print(format!("  {}  ", letter.to_ascii_uppercase())),
+        style::ResetColor
+    )
+}
+
+fn centered<W: Write>(out: &mut W, width: u16, row: u16, text: &str) -> io::Result<()> {
+    let left = width.saturating_sub(text.chars().count() as u16) / 2;
+    queue!(out, cursor::MoveTo(left, row), style::Print(text))
+}
+
+/// The alternate screen in raw mode, put back how it was when this is dropped.
+struct Screen;
+
+impl Screen {
+    fn enter() -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        execute!(io::stdout(), terminal::EnterAlternateScreen, cursor::Hide)?;
+        Ok(Screen)
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        restore();
+    }
+}
+
+/// Best effort: there's nowhere left to report a terminal that won't reset.
+fn restore() {
+    let _ = execute!(
+        io::stdout(),
+        style::ResetColor,
+        cursor::Show,
+        terminal::LeaveAlternateScreen
+    );
+    let _ = terminal::disable_raw_mode();
+}
+
+/// Plays `board` full screen until the player quits or dismisses the result, calling `save` after
+/// every guess or hint. `greeting` is shown as the first message.
+pub fn run<F: FnMut(&Board)>(
+    board: Board,
+    greeting: Option<String>,
+    options: Options,
+    mut save: F,
+) -> io::Result<Board> {
+    // a panic message printed on the alternate screen would vanish with it
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        restore();
+        default_hook(info);
+    }));
+
+    let _screen = Screen::enter()?;
+    let mut app = App::new(board).animated(options.animations).themed(options.theme);
+    if let Some(greeting) = greeting {
+        app.toast(greeting, Instant::now());
+    }
+    draw(&app, &mut io::stdout(), terminal::size()?, Instant::now())?;
+    while !app.is_done() {
+        let mut redraw = app.tick(Instant::now());
+        let timeout = if app.is_animating() { FRAME } else { TICK };
+        if event::poll(timeout)? {
+            match event::read()? {
+                Event::Key(key) => {
+                    if app.handle_key(key, Instant::now()) {
+                        save(app.board());
+                    }
+                    redraw = true;
+                }
+                Event::Resize(..) => redraw = true,
+                Event::Mouse(_) => {}
+            }
+        }
+        if redraw {
+            draw(&app, &mut io::stdout(), terminal::size()?, Instant::now())?;
+        }
+    }
+    Ok(app.into_board())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+    use crate::animation::Kind;
+    use crate::board::{Board, BoardConfig};
+    use crate::dictionary::WebDictionary;
+    use crate::theme::Theme;
+    use crate::tui::{draw, App};
+
+    fn app(word: &str) -> App {
+        App::new(Board::with_dictionary(
+            word.to_string(),
+            BoardConfig::default(),
+            Box::new(WebDictionary::new()),
+        ))
+    }
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    fn type_word(app: &mut App, word: &str, now: Instant) -> bool {
+        for c in word.chars() {
+            app.handle_key(key(KeyCode::Char(c)), now);
+        }
+        app.handle_key(key(KeyCode::Enter), now)
+    }
+
+    fn screen(app: &App) -> String {
+        let mut out = Vec::new();
+        draw(app, &mut out, (80, 24), Instant::now()).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn types_into_the_active_row() {
+        let mut app = app("rusty");
+        let now = Instant::now();
+        for c in "TRUSTY".chars() {
+            app.handle_key(key(KeyCode::Char(c)), now);
+        }
+        assert_eq!(app.input(), "trust");
+        app.handle_key(key(KeyCode::Backspace), now);
+        assert_eq!(app.input(), "trus");
+        app.handle_key(key(KeyCode::Char('3')), now);
+        assert_eq!(app.input(), "trus");
+    }
+
+    #[test]
+    fn guesses_on_enter() {
+        let mut app = app("rusty");
+        let now = Instant::now();
+        assert!(type_word(&mut app, "trust", now));
+        assert_eq!(app.board().guesses(), vec!["trust"]);
+        assert_eq!(app.input(), "");
+    }
+
+    #[test]
+    fn toasts_bad_guesses() {
+        let mut app = app("rusty");
+        let now = Instant::now();
+        assert!(!type_word(&mut app, "zzzzz", now));
+        assert_eq!(app.toast_message(), Some("Not in word list"));
+        assert!(screen(&app).contains(" Not in word list "));
+        assert_eq!(app.input(), "zzzzz");
+        assert_eq!(app.board().guesses_used(), 0);
+
+        assert!(!app.tick(now + Duration::from_secs(1)));
+        assert!(app.tick(now + Duration::from_secs(3)));
+        assert_eq!(app.toast_message(), None);
+
+        app.handle_key(key(KeyCode::Backspace), now);
+        app.handle_key(key(KeyCode::Enter), now);
+        assert_eq!(app.toast_message(), Some("Not enough letters"));
+    }
+
+    #[test]
+    fn hints_and_suggestions() {
+        let mut app = app("rusty");
+        let now = Instant::now();
+        assert!(app.handle_key(key(KeyCode::Char('?')), now));
+        assert_eq!(app.board().hints().len(), 1);
+        assert_eq!(app.input(), "");
+        assert!(!app.handle_key(key(KeyCode::Tab), now));
+        assert!(app.toast_message().unwrap().starts_with("Try "));
+    }
+
+    #[test]
+    fn shows_the_result_when_its_over() {
+        let mut app = app("rusty");
+        let now = Instant::now();
+        type_word(&mut app, "rusty", now);
+        let screen = screen(&app);
+        assert!(screen.contains("You won in 1/6!"));
+        assert!(screen.contains("🟩🟩🟩🟩🟩"));
+        assert!(screen.contains("Press Enter to quit"));
+
+        app.handle_key(key(KeyCode::Char('a')), now);
+        assert_eq!(app.input(), "");
+        assert!(!app.is_done());
+        app.handle_key(key(KeyCode::Enter), now);
+        assert!(app.is_done());
+    }
+
+    #[test]
+    fn quits_on_ctrl_c() {
+        let mut app = app("rusty");
+        app.handle_key(KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL), Instant::now());
+        assert!(app.is_done());
+        assert_eq!(app.input(), "");
+    }
+
+    #[test]
+    fn reveals_then_bounces_a_win() {
+        let mut app = app("rusty").animated(true);
+        let now = Instant::now();
+        type_word(&mut app, "rusty", now);
+        assert_eq!(app.animation().map(|a| (a.kind, a.row)), Some((Kind::Reveal, 0)));
+        assert!(app.is_animating());
+        assert!(!screen(&app).contains("Press Enter to quit"));
+
+        let revealed = now + Duration::from_secs(2);
+        assert!(app.tick(revealed));
+        assert_eq!(app.animation().map(|a| a.kind), Some(Kind::Bounce));
+        assert!(screen(&app).contains("Press Enter to quit"));
+        app.tick(revealed + Duration::from_secs(1));
+        assert!(!app.is_animating());
+    }
+
+    #[test]
+    fn shakes_a_bad_guess() {
+        let mut app = app("rusty").animated(true);
+        let now = Instant::now();
+        type_word(&mut app, "trust", now);
+        app.tick(now + Duration::from_secs(2));
+        assert!(!app.is_animating());
+
+        type_word(&mut app, "zzzzz", now);
+        assert_eq!(app.animation().map(|a| (a.kind, a.row)), Some((Kind::Shake, 1)));
+        app.tick(now + Duration::from_secs(1));
+        assert!(!app.is_animating());
+    }
+
+    #[test]
+    fn animations_are_off_unless_asked_for() {
+        let mut app = app("rusty");
+        let now = Instant::now();
+        type_word(&mut app, "zzzzz", now);
+        assert!(!app.is_animating());
+        let mut app = self::app("rusty");
+        type_word(&mut app, "rusty", now);
+        assert!(!app.is_animating());
+    }
+
+    #[test]
+    fn draws_in_the_theme() {
+        let mut app = app("rusty").themed(Theme::HighContrast);
+        type_word(&mut app, "rusty", Instant::now());
+        // the orange background of a correct letter
+        assert!(screen(&app).contains("\x1b[48;2;245;121;58m"));
+        assert!(screen(&app).contains("🟧🟧🟧🟧🟧"));
+    }
+}